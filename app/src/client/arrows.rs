@@ -1,9 +1,22 @@
-use chess::Move;
+use chess::{MarkupArrow, MarkupColor, Move};
 use palette::{LinSrgb, LinSrgba, WithAlpha};
 
 pub(super) const ALPHA: f64 = 0.75;
 pub(super) const ANALYSIS_COLOR: LinSrgb<f64> = LinSrgb::new(0.11, 0.53, 0.73);
 pub(super) const USER_COLOR: LinSrgb<f64> = LinSrgb::new(0.99, 0.62, 0.01);
+const GREEN: LinSrgb<f64> = LinSrgb::new(0.08, 0.47, 0.11);
+const RED: LinSrgb<f64> = LinSrgb::new(0.53, 0.13, 0.13);
+const BLUE: LinSrgb<f64> = LinSrgb::new(0.0, 0.19, 0.53);
+
+/// The color arrows of an annotation are drawn in, the user's own being yellow.
+fn get_markup_color(color: MarkupColor) -> LinSrgb<f64> {
+    match color {
+        MarkupColor::Green => GREEN,
+        MarkupColor::Red => RED,
+        MarkupColor::Yellow => USER_COLOR,
+        MarkupColor::Blue => BLUE,
+    }
+}
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub(super) struct ArrowData {
@@ -30,6 +43,25 @@ impl ArrowData {
         }
     }
 
+    pub(super) fn from_markup(arrow: &MarkupArrow) -> Self {
+        Self {
+            mv: Move::new(arrow.from, arrow.to),
+            color: get_markup_color(arrow.color).with_alpha(ALPHA),
+        }
+    }
+
+    pub(super) fn to_markup(&self) -> MarkupArrow {
+        let color = MarkupColor::ALL
+            .into_iter()
+            .find(|&color| get_markup_color(color).with_alpha(ALPHA) == self.color)
+            .unwrap_or(MarkupColor::Yellow);
+        MarkupArrow {
+            from: self.mv.from,
+            to: self.mv.to,
+            color,
+        }
+    }
+
     pub(super) fn has_length(&self) -> bool {
         self.mv.from != self.mv.to
     }
@@ -49,6 +81,14 @@ impl Arrows {
         }
     }
 
+    /// The arrows of an annotation.
+    pub(super) fn from_markup(arrows: &[MarkupArrow]) -> Self {
+        Self {
+            showing: arrows.len(),
+            arrows: arrows.iter().map(ArrowData::from_markup).collect(),
+        }
+    }
+
     pub(super) fn with_size(n: usize) -> Self {
         Self::new(vec![Move::default(); n])
     }
@@ -0,0 +1,13 @@
+use std::sync::{Arc, Mutex};
+
+use async_std::task::spawn_blocking;
+use chess::{Engine, Game, Move, SearchLimits};
+
+/// Searches on a thread of its own, so that the board keeps responding meanwhile.
+pub(crate) async fn search_bot_move(
+    engine: Arc<Mutex<Engine>>,
+    position: Game,
+    limits: SearchLimits,
+) -> Option<Move> {
+    spawn_blocking(move || engine.lock().unwrap().get_best_move(&position, limits)).await
+}
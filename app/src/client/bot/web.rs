@@ -0,0 +1,37 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_std::task::sleep;
+use chess::{Engine, Game, Move, SearchLimits};
+
+/// Searches one depth at a time, letting the browser handle events in between, as the search
+/// cannot leave the UI thread here. The transposition table keeps each depth from starting over.
+pub(crate) async fn search_bot_move(
+    engine: Arc<Mutex<Engine>>,
+    position: Game,
+    limits: SearchLimits,
+) -> Option<Move> {
+    let start = js_sys::Date::now();
+    let mut best_move = None;
+    for depth in 1..=limits.depth.unwrap_or(u8::MAX) {
+        let elapsed = Duration::from_secs_f64((js_sys::Date::now() - start) / 1000.0);
+        let time = match limits.time {
+            Some(time) if elapsed >= time => break,
+            time => time.map(|time| time - elapsed),
+        };
+        let depth_limits = SearchLimits {
+            depth: Some(depth),
+            time,
+            ..limits
+        };
+        best_move = engine
+            .lock()
+            .unwrap()
+            .get_best_move(&position, depth_limits)
+            .or(best_move);
+        sleep(Duration::ZERO).await;
+    }
+    best_move
+}
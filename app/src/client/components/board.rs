@@ -1,7 +1,17 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use async_std::channel::{unbounded, Receiver, Sender};
-use chess::{Color, Game, Move, Piece, PlayerKind, Position};
+use async_std::{
+    channel::{unbounded, Receiver, Sender},
+    task::sleep,
+};
+use chess::{
+    Color, Engine, Game, MarkupColor, MarkupSquare, Move, Piece, PlayerKind, Position,
+    SearchLimits, Variant,
+};
 use dioxus::{
     html::{
         geometry::ElementPoint,
@@ -17,25 +27,29 @@ use once_cell::sync::Lazy;
 
 use super::super::{
     arrows::{ArrowData, Arrows},
-    components::{Arrow, BoardSquare, Piece},
-    game_socket::create_game_socket,
+    bot::search_bot_move,
+    components::{Arrow, BoardSquare, Piece, PocketTray, PromotionPicker},
+    game_socket::{create_game_socket, send_game_message},
     mouse_click::MouseClick,
-    shared_states::{Analyze, BoardSize, GameId, Perspective},
+    shared_states::{Analyze, BoardSize, BotLevel, Disconnected, GameId, Perspective},
     stockfish::{
         core::{on_game_changed, toggle_stockfish},
         interface::Process,
         Eval,
     },
 };
-use crate::client::shared_states::Settings;
+use crate::{client::shared_states::Settings, common::game_message::GameMessage};
 
 pub(crate) type Channel<T> = (Sender<T>, Receiver<T>);
 
-// Channel for sending moves to `game_socket` to be sent to a remote player
-static MOVE_CHANNEL: Lazy<Channel<Move>> = Lazy::new(unbounded);
 // Channel for telling dragged pieces how far they have been dragged
 static DRAG_CHANNEL: Lazy<Channel<ElementPoint>> = Lazy::new(unbounded);
 
+// How long the bot waits before searching, so that the board shows the move it replies to
+const BOT_MOVE_DELAY: Duration = Duration::from_millis(300);
+// How long the bot may search at any level
+const BOT_MOVE_TIME: Duration = Duration::from_secs(1);
+
 #[derive(Props, PartialEq)]
 pub(crate) struct BoardProps {
     white_player_kind: PlayerKind,
@@ -56,7 +70,11 @@ pub(crate) struct BoardHooks<'a> {
     pub(crate) hovered_position: &'a UseState<Option<Position>>,
     pub(crate) board_size: u32,
     pub(crate) perspective: Color,
-    pub(crate) selected_squares: &'a UseRef<HashSet<Position>>,
+    pub(crate) selected_squares: &'a UseRef<HashMap<Position, MarkupColor>>,
+    /// The path of the position whose markup is on the board.
+    pub(crate) markup_path: &'a UseRef<Option<Vec<usize>>>,
+    pub(crate) pending_promotion: &'a UseState<Option<Move>>,
+    pub(crate) dropping_piece: &'a UseState<Option<Piece>>,
 }
 
 pub(crate) fn Board(cx: Scope<BoardProps>) -> Element {
@@ -76,8 +94,13 @@ pub(crate) fn Board(cx: Scope<BoardProps>) -> Element {
         hovered_position: use_state::<Option<Position>>(cx, || None),
         board_size: **use_shared_state::<BoardSize>(cx)?.read(),
         perspective: **use_shared_state::<Perspective>(cx)?.read(),
-        selected_squares: use_ref::<HashSet<Position>>(cx, HashSet::new),
+        selected_squares: use_ref::<HashMap<Position, MarkupColor>>(cx, HashMap::new),
+        markup_path: use_ref::<Option<Vec<usize>>>(cx, || None),
+        pending_promotion: use_state::<Option<Move>>(cx, || None),
+        dropping_piece: use_state::<Option<Piece>>(cx, || None),
     };
+    let has_pockets = hooks.game.read().get_variant() == Variant::Crazyhouse;
+    load_markup(&hooks);
 
     use_effect(cx, use_shared_state::<Analyze>(cx).unwrap(), |analyze| {
         toggle_stockfish(
@@ -95,11 +118,39 @@ pub(crate) fn Board(cx: Scope<BoardProps>) -> Element {
             hooks.analysis_arrows.to_owned(),
         )
     });
+    let remote_player = match (cx.props.white_player_kind, cx.props.black_player_kind) {
+        (PlayerKind::Remote, _) => Some(Color::White),
+        (_, PlayerKind::Remote) => Some(Color::Black),
+        _ => None,
+    };
+    let disconnected = use_shared_state::<Disconnected>(cx)?;
     use_future(cx, use_shared_state::<GameId>(cx).unwrap(), |game_id| {
-        create_game_socket(hooks.game.to_owned(), game_id, &MOVE_CHANNEL.1)
+        create_game_socket(
+            hooks.game.to_owned(),
+            game_id,
+            disconnected.to_owned(),
+            remote_player,
+        )
+    });
+    let bot_player = match (cx.props.white_player_kind, cx.props.black_player_kind) {
+        (PlayerKind::Bot, _) => Some(Color::White),
+        (_, PlayerKind::Bot) => Some(Color::Black),
+        _ => None,
+    };
+    let bot_level = **use_shared_state::<BotLevel>(cx)?.read();
+    let engine = use_ref(cx, || Arc::new(Mutex::new(Engine::new())));
+    use_effect(cx, hooks.game, |game| {
+        play_bot_move(game, engine.read().clone(), bot_player, bot_level)
     });
 
     cx.render(rsx! {
+        // the opponent's pocket
+        if has_pockets {
+            PocketTray {
+                player: !hooks.perspective,
+                on_pick: move |piece| pick_from_pocket(cx.props, &hooks, !hooks.perspective, piece),
+            }
+        }
         // div for widget
         div {
             class: "board-container",
@@ -110,6 +161,7 @@ pub(crate) fn Board(cx: Scope<BoardProps>) -> Element {
             onmousedown: move |event| handle_on_mouse_down_event(&hooks, event),
             onmouseup: move |event| handle_on_mouse_up_event(cx.props, &hooks, event),
             onmousemove: move |event| handle_on_mouse_move_event(&hooks, event),
+            onmouseleave: move |_| cancel_drop(&hooks),
             onkeydown: move |event| handle_on_key_down(cx, &hooks, event),
             // board
             img {
@@ -137,6 +189,14 @@ pub(crate) fn Board(cx: Scope<BoardProps>) -> Element {
                     }),
                 }
             },
+            // piece dragged from a pocket
+            for (piece, pos) in hooks.dropping_piece.get().zip(*hooks.hovered_position.get()) {
+                Piece {
+                    image: get_piece_image_file(&hooks.settings.read().piece_theme, piece),
+                    top_left_starting: _to_point(&hooks, &pos),
+                    is_dragging: true,
+                }
+            }
             // arrows
             for data in hooks.arrows.read().get().into_iter()
                 .chain(hooks.drawing_arrow.read().into_iter())
@@ -147,6 +207,22 @@ pub(crate) fn Board(cx: Scope<BoardProps>) -> Element {
             for data in hooks.analysis_arrows.read().get().into_iter() {
                 Arrow { data: data }
             }
+            // promotion choices
+            for mv in *hooks.pending_promotion.get() {
+                PromotionPicker {
+                    mv: mv,
+                    player: hooks.game.read().get_current_player(),
+                    on_select: move |piece| complete_promotion(cx.props, &hooks, piece),
+                    on_cancel: move |_| hooks.pending_promotion.set(None),
+                }
+            }
+        }
+        // the player's own pocket
+        if has_pockets {
+            PocketTray {
+                player: hooks.perspective,
+                on_pick: move |piece| pick_from_pocket(cx.props, &hooks, hooks.perspective, piece),
+            }
         }
     })
 }
@@ -155,7 +231,7 @@ fn get_board_image(theme: &str) -> String {
     format!("images/boards/{theme}/{theme}.png")
 }
 
-fn get_piece_image_file(theme: &str, piece: Piece) -> String {
+pub(crate) fn get_piece_image_file(theme: &str, piece: Piece) -> String {
     let piece_img = match piece {
         Piece::Pawn(Color::White) => "pw",
         Piece::Knight(Color::White) => "nw",
@@ -208,6 +284,7 @@ fn handle_on_key_down(cx: Scope<BoardProps>, hooks: &BoardHooks, event: Event<Ke
     #[cfg(not(feature = "desktop"))]
     let _ = &cx;
     match event.key() {
+        Key::Escape => hooks.pending_promotion.set(None),
         Key::ArrowLeft => hooks.game.write().go_back_a_move(),
         Key::ArrowRight => hooks.game.write().go_forward_a_move(),
         Key::ArrowUp => hooks.game.write().resume(),
@@ -215,9 +292,11 @@ fn handle_on_key_down(cx: Scope<BoardProps>, hooks: &BoardHooks, event: Event<Ke
         Key::Character(c) => match c.as_str() {
             "z" if event.modifiers() == Modifiers::CONTROL => {
                 hooks.arrows.write().undo();
+                save_markup(hooks);
             }
             "y" if event.modifiers() == Modifiers::CONTROL => {
                 hooks.arrows.write().redo();
+                save_markup(hooks);
             }
             #[cfg(feature = "desktop")]
             "q" if event.modifiers() == Modifiers::CONTROL => {
@@ -247,21 +326,88 @@ fn drop_piece(
 ) {
     let from = _to_position(hooks, point);
     let to = _to_position(hooks, &event.element_coordinates());
+    if can_move(props, hooks) && hooks.game.read().is_promotion(&from, &to) {
+        // wait for the user to pick a piece before moving
+        hooks.pending_promotion.set(Some(Move::new(from, to)));
+    } else {
+        play_move(props, hooks, Move::new(from, to));
+    }
+}
+
+fn pick_from_pocket(props: &BoardProps, hooks: &BoardHooks, player: Color, piece: Piece) {
+    if can_move(props, hooks) && hooks.game.read().get_current_player() == player {
+        hooks.dropping_piece.set(Some(piece));
+    }
+}
+
+fn cancel_drop(hooks: &BoardHooks) {
+    if hooks.dropping_piece.is_some() {
+        hooks.dropping_piece.set(None);
+        hooks.hovered_position.set(None);
+    }
+}
+
+fn complete_promotion(props: &BoardProps, hooks: &BoardHooks, piece: Piece) {
+    if let Some(mv) = *hooks.pending_promotion.get() {
+        play_move(props, hooks, Move::with_promotion(mv.from, mv.to, piece));
+    }
+    hooks.pending_promotion.set(None);
+}
+
+fn play_move(props: &BoardProps, hooks: &BoardHooks, mv: Move) {
     let opponent_player_kind = match hooks.game.read().get_current_player() {
         Color::White => props.black_player_kind,
         Color::Black => props.white_player_kind,
     };
-    let mv = Move::new(from, to);
     if can_move(props, hooks) && hooks.game.read().is_move_valid(&mv).is_ok() {
-        hooks.game.write().move_piece(from, to).ok();
+        hooks.game.write().move_piece(mv).ok();
         if opponent_player_kind == PlayerKind::Remote {
-            spawn(async move {
-                if let Err(e) = MOVE_CHANNEL.0.send(mv).await {
-                    log::error!("Failed to send move: {e}")
-                }
-            })
+            send_game_message(GameMessage::Move(mv));
+        }
+    }
+}
+
+/// Lets the built-in engine reply when it is the bot's turn in the game.
+async fn play_bot_move(
+    game: UseSharedState<Game>,
+    engine: Arc<Mutex<Engine>>,
+    bot_player: Option<Color>,
+    level: u8,
+) {
+    let is_bot_turn = |game: &Game| !game.game_over() && Some(game.get_real_player()) == bot_player;
+    if !is_bot_turn(&game.read()) {
+        return;
+    }
+    sleep(BOT_MOVE_DELAY).await;
+    // the game may have moved on in the meantime
+    let mut position = game.read().clone();
+    if !is_bot_turn(&position) {
+        return;
+    }
+    position.resume();
+    let fen = position.get_fen_str();
+    let limits = SearchLimits {
+        depth: Some(level),
+        time: Some(BOT_MOVE_TIME),
+        nodes: None,
+    };
+    let Some(mv) = search_bot_move(engine, position, limits).await else {
+        return;
+    };
+    let mut game = game.write();
+    // the bot continues the game, and a player looking at another move stays there
+    let path = game.get_current_path().to_vec();
+    let replaying = game.is_replaying();
+    game.resume();
+    // the game may have moved on during the search too
+    if is_bot_turn(&game) && game.get_fen_str() == fen {
+        if let Err(err) = game.move_piece(mv) {
+            log::error!("Bot move {mv:?} failed: {err}");
         }
     }
+    if replaying {
+        game.go_to_move(&path).ok();
+    }
 }
 
 fn complete_arrow(hooks: &BoardHooks) {
@@ -273,6 +419,48 @@ fn complete_arrow(hooks: &BoardHooks) {
     *hooks.drawing_arrow.write() = None;
 }
 
+/// Shows the arrows and highlighted squares of the current position after going to another one.
+fn load_markup(hooks: &BoardHooks) {
+    let game = hooks.game.read();
+    let path = game.get_current_path();
+    if hooks.markup_path.read().as_deref() == Some(path) {
+        return;
+    }
+    *hooks.markup_path.write_silent() = Some(path.to_vec());
+    let annotation = game.get_annotation();
+    *hooks.arrows.write_silent() = Arrows::from_markup(&annotation.arrows);
+    *hooks.selected_squares.write_silent() = annotation
+        .squares
+        .iter()
+        .map(|square| (square.position, square.color))
+        .collect();
+}
+
+/// Keeps the arrows and highlighted squares on the board in the annotation of the current
+/// position.
+fn save_markup(hooks: &BoardHooks) {
+    // the position itself does not change, so the analysis goes on
+    let mut game = hooks.game.write_silent();
+    let mut annotation = game.get_annotation().clone();
+    annotation.arrows = hooks
+        .arrows
+        .read()
+        .get()
+        .iter()
+        .map(ArrowData::to_markup)
+        .collect();
+    annotation.squares = hooks
+        .selected_squares
+        .read()
+        .iter()
+        .map(|(&position, &color)| MarkupSquare { position, color })
+        .collect();
+    annotation
+        .squares
+        .sort_by_key(|square| (square.position.y, square.position.x));
+    game.set_annotation(annotation);
+}
+
 async fn send_dragging_point(hooks: &BoardHooks<'_>, event: Event<MouseData>) {
     DRAG_CHANNEL
         .0
@@ -285,6 +473,7 @@ async fn send_dragging_point(hooks: &BoardHooks<'_>, event: Event<MouseData>) {
 }
 
 fn handle_on_mouse_down_event(hooks: &BoardHooks, event: Event<MouseData>) {
+    cancel_drop(hooks);
     let mouse_down = MouseClick::from(event.clone());
     if mouse_down.kind.contains(MouseButton::Primary) {
         hooks
@@ -301,24 +490,35 @@ fn handle_on_mouse_down_event(hooks: &BoardHooks, event: Event<MouseData>) {
 }
 
 fn handle_on_mouse_up_event(props: &BoardProps, hooks: &BoardHooks, event: Event<MouseData>) {
+    if let Some(piece) = *hooks.dropping_piece.get() {
+        let to = _to_position(hooks, &event.element_coordinates());
+        play_move(props, hooks, Move::with_drop(piece, to));
+        cancel_drop(hooks);
+        return;
+    }
     if let Some(mouse_down) = hooks.mouse_down_state.get() {
         if mouse_down.kind.contains(MouseButton::Primary) {
             drop_piece(props, hooks, &event, &mouse_down.point);
+            // only hides the markup, the annotation keeps it for when the position is shown again
             hooks.arrows.write().clear();
             hooks.selected_squares.write().clear();
         } else if mouse_down.kind.contains(MouseButton::Secondary) {
             let from = _to_position(hooks, &mouse_down.point);
             let to = _to_position(hooks, &event.element_coordinates());
             if from == to {
-                let pos_opt = hooks.selected_squares.read().get(&from).cloned();
-                if let Some(pos) = pos_opt {
-                    hooks.selected_squares.write().remove(&pos);
+                let is_selected = hooks.selected_squares.read().contains_key(&from);
+                if is_selected {
+                    hooks.selected_squares.write().remove(&from);
                 } else {
-                    hooks.selected_squares.write().insert(from);
+                    hooks
+                        .selected_squares
+                        .write()
+                        .insert(from, MarkupColor::Red);
                 }
             } else {
                 complete_arrow(hooks);
             }
+            save_markup(hooks);
         }
         hooks.mouse_down_state.set(None);
         hooks.selected_piece.set(None);
@@ -328,7 +528,17 @@ fn handle_on_mouse_up_event(props: &BoardProps, hooks: &BoardHooks, event: Event
 
 fn handle_on_mouse_move_event(hooks: &BoardHooks, event: Event<MouseData>) {
     let pos = _to_position(hooks, &event.element_coordinates());
-    if let Some(mouse_down) = hooks.mouse_down_state.get() {
+    if hooks.dropping_piece.is_some() {
+        // the button was released off the board
+        if !event.held_buttons().contains(MouseButton::Primary) {
+            cancel_drop(hooks);
+            return;
+        }
+        block_on(send_dragging_point(hooks, event));
+        if *hooks.hovered_position.get() != Some(pos) {
+            hooks.hovered_position.set(Some(pos));
+        }
+    } else if let Some(mouse_down) = hooks.mouse_down_state.get() {
         if mouse_down.kind.contains(MouseButton::Primary) {
             block_on(send_dragging_point(hooks, event));
             if hooks.hovered_position.is_none() || hooks.hovered_position.unwrap() != pos {
@@ -342,10 +552,20 @@ fn handle_on_mouse_move_event(hooks: &BoardHooks, event: Event<MouseData>) {
     }
 }
 
+/// The squares the piece being dragged, from the board or from a pocket, may go to.
+fn get_valid_destinations(hooks: &BoardHooks) -> Vec<Position> {
+    let game = hooks.game.read();
+    if let Some(piece) = *hooks.dropping_piece.get() {
+        game.get_valid_drop_squares(piece)
+    } else if let Some(from) = *hooks.selected_piece.read() {
+        game.get_valid_destinations_for_piece(&from)
+    } else {
+        vec![]
+    }
+}
+
 fn is_valid_destination(hooks: &BoardHooks, pos: Position) -> bool {
-    let from = hooks.selected_piece.read().unwrap();
-    let destinations = hooks.game.read().get_valid_destinations_for_piece(&from);
-    destinations.contains(&pos)
+    get_valid_destinations(hooks).contains(&pos)
 }
 
 pub(crate) fn get_center(board_size: u32, perspective: Color, pos: &Position) -> ElementPoint {
@@ -358,11 +578,9 @@ pub(crate) fn get_center(board_size: u32, perspective: Color, pos: &Position) ->
 fn get_highlighted_squares_info(props: &BoardProps, hooks: &BoardHooks) -> Vec<(Position, String)> {
     let game = hooks.game.read();
     let mut info = game.get_highlighted_squares_info();
-    if can_move(props, hooks)
-        && let Some(pos) = &*hooks.selected_piece.read()
-    {
+    if can_move(props, hooks) {
         info.extend(
-            game.get_valid_destinations_for_piece(pos)
+            get_valid_destinations(hooks)
                 .into_iter()
                 .map(|pos| (pos, "destination-square".to_string())),
         );
@@ -372,10 +590,19 @@ fn get_highlighted_squares_info(props: &BoardProps, hooks: &BoardHooks) -> Vec<(
             .selected_squares
             .read()
             .iter()
-            .map(|&pos| (pos, "selected-square".to_string())),
+            .map(|(&pos, &color)| (pos, get_selected_square_class(color).to_string())),
     );
     info.extend(
         (0..8).flat_map(|x| (0..8).map(move |y| (Position::new(x, y), "text-square".into()))),
     );
     info
 }
+
+fn get_selected_square_class(color: MarkupColor) -> &'static str {
+    match color {
+        MarkupColor::Green => "selected-square-green",
+        MarkupColor::Red => "selected-square-red",
+        MarkupColor::Yellow => "selected-square-yellow",
+        MarkupColor::Blue => "selected-square-blue",
+    }
+}
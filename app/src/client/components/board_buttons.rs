@@ -1,11 +1,18 @@
-use chess::{Color, Game, Player, PlayerKind};
+use chess::{Color, Game, Player, PlayerKind, Variant};
 use dioxus::prelude::*;
 
 use crate::{
-    client::shared_states::{Analyze, BoardSize, GameId, Perspective},
+    client::{
+        download::save_file,
+        game_socket::send_game_message,
+        shared_states::{Analyze, BoardSize, BotLevel, GameId, Perspective},
+    },
+    common::game_message::GameMessage,
     server::server_functions::setup_remote_game,
 };
 
+const MAX_BOT_LEVEL: u8 = 8;
+
 #[component]
 pub(crate) fn BoardButtons(
     cx: Scope,
@@ -17,6 +24,41 @@ pub(crate) fn BoardButtons(
     let perspective = use_shared_state::<Perspective>(cx)?;
     let game = use_shared_state::<Game>(cx)?;
     let game_id = use_shared_state::<GameId>(cx)?;
+    let bot_level = use_shared_state::<BotLevel>(cx)?;
+    let current_bot_level = **bot_level.read();
+    let local_player = get_local_player(&game.read(), &white_player, &black_player);
+    let opponent_is_remote = match local_player {
+        Color::White => black_player.read().kind == PlayerKind::Remote,
+        Color::Black => white_player.read().kind == PlayerKind::Remote,
+    };
+    let send_to_opponent = move |message| {
+        if opponent_is_remote {
+            send_game_message(message);
+        }
+    };
+    let game_over = game.read().game_over();
+    let draw_offer = game.read().get_draw_offer();
+    let can_claim_draw = game.read().can_claim_draw();
+    let takeback_request = game.read().get_takeback_request();
+    let opponent_is_local = white_player.read().kind == PlayerKind::Local
+        && black_player.read().kind == PlayerKind::Local;
+    // a draw is offered after moving, for the opponent to answer on their turn, so in a local
+    // game it comes from the player who just moved
+    let draw_offerer = match opponent_is_local {
+        true => !game.read().get_real_player(),
+        false => local_player,
+    };
+    let can_offer_draw = draw_offer.is_none() && game.read().get_real_player() != draw_offerer;
+    // the last move in a local game, whoever made it, the local player's otherwise
+    let takeback_length = match opponent_is_local {
+        true => game
+            .read()
+            .get_takeback_length(!game.read().get_real_player()),
+        false => game.read().get_takeback_length(local_player),
+    };
+    let bot_plays =
+        white_player.read().kind == PlayerKind::Bot || black_player.read().kind == PlayerKind::Bot;
+    let variant = game.read().get_variant();
 
     cx.render(rsx! {
         div { class: "board-buttons-container", style: "width: {board_size}px",
@@ -27,7 +69,8 @@ pub(crate) fn BoardButtons(
                         match setup_remote_game().await {
                             Ok(info) => {
                                 log::info!("Setting up remote game: {info:?}");
-                                game.write().reset();
+                                // remote games are played by the standard rules
+                                *game.write() = Game::new();
                                 **game_id.write() = Some(info.game_id);
                                 let player = match info.local_color {
                                     Color::White => black_player.to_owned(),
@@ -43,12 +86,163 @@ pub(crate) fn BoardButtons(
                 },
                 "Play Remote"
             }
+            button { class: "button",
+                hidden: opponent_is_remote,
+                onclick: move |_| {
+                    // the bot takes black in a new game, or goes back to a local game
+                    let bot_kind = match bot_plays {
+                        true => PlayerKind::Local,
+                        false => PlayerKind::Bot,
+                    };
+                    game.write().reset();
+                    white_player.write().kind = PlayerKind::Local;
+                    black_player.write().kind = bot_kind;
+                    **perspective.write() = Color::White;
+                    **analyze.write() = false;
+                },
+                if bot_plays { "Play Local" } else { "Play Bot" }
+            }
+            if bot_plays {
+                rsx! {
+                    select { class: "select",
+                        onchange: move |event| {
+                            if let Ok(level) = event.value().parse() {
+                                **bot_level.write() = level;
+                            }
+                        },
+                        for level in 1..=MAX_BOT_LEVEL {
+                            option {
+                                value: "{level}",
+                                selected: level == current_bot_level,
+                                "Level {level}"
+                            }
+                        }
+                    }
+                }
+            }
+            select { class: "select",
+                hidden: opponent_is_remote,
+                onchange: move |event| {
+                    let new_game = event
+                        .value()
+                        .parse()
+                        .and_then(|variant| Game::builder().variant(variant).build());
+                    match new_game {
+                        Ok(new_game) => *game.write() = new_game,
+                        Err(err) => log::error!("Error starting new game: {err}"),
+                    }
+                },
+                for option_variant in Variant::ALL {
+                    option {
+                        value: "{option_variant}",
+                        selected: option_variant == variant,
+                        "{option_variant}"
+                    }
+                }
+            }
             button { class: "button",
                 onclick: |_| perspective.with_mut(|perspective| **perspective = !**perspective),
                 "Flip Board"
             }
             button { class: "button",
-                hidden: !game.read().game_over()
+                hidden: game_over,
+                onclick: move |_| {
+                    if game.write().resign(local_player).is_ok() {
+                        send_to_opponent(GameMessage::Resign(local_player));
+                    }
+                },
+                "Resign"
+            }
+            if can_claim_draw {
+                rsx! {
+                    button { class: "button",
+                        onclick: move |_| {
+                            if game.write().claim_draw().is_ok() {
+                                send_to_opponent(GameMessage::ClaimDraw);
+                            }
+                        },
+                        "Claim Draw"
+                    }
+                }
+            }
+            if draw_offer.is_some_and(|player| player != local_player) {
+                rsx! {
+                    button { class: "button",
+                        onclick: move |_| {
+                            if game.write().respond_to_draw(local_player, true).is_ok() {
+                                send_to_opponent(GameMessage::RespondToDraw(true));
+                            }
+                        },
+                        "Accept Draw"
+                    }
+                    button { class: "button",
+                        onclick: move |_| {
+                            if game.write().respond_to_draw(local_player, false).is_ok() {
+                                send_to_opponent(GameMessage::RespondToDraw(false));
+                            }
+                        },
+                        "Decline Draw"
+                    }
+                }
+            } else {
+                rsx! {
+                    button { class: "button",
+                        hidden: game_over,
+                        disabled: !can_offer_draw,
+                        onclick: move |_| {
+                            if game.write().offer_draw(draw_offerer).is_ok() {
+                                send_to_opponent(GameMessage::OfferDraw(draw_offerer));
+                            }
+                        },
+                        if draw_offer.is_some() { "Draw Offered" } else { "Offer Draw" }
+                    }
+                }
+            }
+            if takeback_request.is_some_and(|player| player != local_player) {
+                rsx! {
+                    button { class: "button",
+                        onclick: move |_| {
+                            if game.write().respond_to_takeback(local_player, true).is_ok() {
+                                send_to_opponent(GameMessage::RespondToTakeback(true));
+                            }
+                        },
+                        "Accept Takeback"
+                    }
+                    button { class: "button",
+                        onclick: move |_| {
+                            if game.write().respond_to_takeback(local_player, false).is_ok() {
+                                send_to_opponent(GameMessage::RespondToTakeback(false));
+                            }
+                        },
+                        "Decline Takeback"
+                    }
+                }
+            } else {
+                rsx! {
+                    button { class: "button",
+                        // a local game can go on after it ended
+                        hidden: game_over && !opponent_is_local,
+                        disabled: takeback_request.is_some() || takeback_length.is_none(),
+                        onclick: move |_| {
+                            if opponent_is_remote {
+                                // the remote player has to agree
+                                if game.write().request_takeback(local_player).is_ok() {
+                                    send_to_opponent(GameMessage::RequestTakeback(local_player));
+                                }
+                            } else if let Some(n) = takeback_length {
+                                game.write().take_back(n).ok();
+                            }
+                        },
+                        if takeback_request.is_some() { "Takeback Requested" } else { "Takeback" }
+                    }
+                }
+            }
+            button { class: "button",
+                onclick: |_| export_pgn(&game.read(), &white_player.read(), &black_player.read()),
+                "Export PGN"
+            }
+            button { class: "button",
+                hidden: !game_over
                     && (white_player.read().kind != PlayerKind::Local
                         || black_player.read().kind != PlayerKind::Local),
                 onclick: |_| analyze.with_mut(|analyze| **analyze = !**analyze),
@@ -71,6 +265,31 @@ pub(crate) fn BoardButtons(
     })
 }
 
+fn export_pgn(game: &Game, white_player: &Player, black_player: &Player) {
+    let tags: Vec<_> = [("White", &white_player.name), ("Black", &black_player.name)]
+        .into_iter()
+        .filter(|(_, name)| !name.is_empty())
+        .map(|(tag, name)| (tag, name.as_str()))
+        .collect();
+    save_file("game.pgn", &game.to_pgn_with_tags(&tags));
+}
+
+/// The player the buttons act for: the local side of a game against a remote or bot opponent,
+/// otherwise the player to move.
+fn get_local_player(
+    game: &Game,
+    white_player: &UseLock<Player>,
+    black_player: &UseLock<Player>,
+) -> Color {
+    if white_player.read().kind != PlayerKind::Local {
+        Color::Black
+    } else if black_player.read().kind != PlayerKind::Local {
+        Color::White
+    } else {
+        game.get_real_player()
+    }
+}
+
 fn get_default_perspective(
     white_player: &UseLock<Player>,
     black_player: &UseLock<Player>,
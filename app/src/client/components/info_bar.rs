@@ -1,11 +1,25 @@
+use chess::Game;
 use dioxus::prelude::*;
 
 use super::{RoundList, Timer};
+use crate::client::shared_states::Disconnected;
 
 #[component]
 pub(crate) fn InfoBar(cx: Scope, is_local_game: bool) -> Element {
+    let result = use_shared_state::<Game>(cx)?.read().result();
+    let disconnected = **use_shared_state::<Disconnected>(cx)?.read();
+    let show_disconnected = disconnected && result.is_none() && !is_local_game;
+
     cx.render(rsx! {
         div { class: "info-bar-container",
+            result.map(|result| rsx! {
+                p { class: "game-over-banner", "{result}" }
+            }),
+            if show_disconnected {
+                rsx! {
+                    p { class: "disconnected-banner", "Disconnected from the game" }
+                }
+            }
             if !is_local_game {
                 Timer {}
             }
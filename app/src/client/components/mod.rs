@@ -8,6 +8,8 @@ mod eval_bar;
 mod info_bar;
 pub(super) mod nav_bar;
 mod piece;
+mod pocket_tray;
+mod promotion_picker;
 mod round_list;
 pub(super) mod settings;
 mod timer;
@@ -21,6 +23,8 @@ pub(super) use board_square::BoardSquare;
 pub(super) use eval_bar::EvalBar;
 pub(super) use info_bar::InfoBar;
 pub(super) use piece::Piece;
+pub(super) use pocket_tray::PocketTray;
+pub(super) use promotion_picker::PromotionPicker;
 pub(super) use round_list::RoundList;
 pub(super) use settings::Settings;
 pub(super) use timer::Timer;
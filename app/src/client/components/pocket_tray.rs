@@ -0,0 +1,44 @@
+use chess::{Color, Game, Piece};
+use dioxus::prelude::*;
+
+use super::super::{
+    components::board::get_piece_image_file,
+    shared_states::{BoardSize, Settings},
+};
+
+#[component]
+pub(crate) fn PocketTray<'a>(
+    cx: Scope<'a>,
+    player: Color,
+    on_pick: EventHandler<'a, Piece>,
+) -> Element<'a> {
+    let board_size = **use_shared_state::<BoardSize>(cx)?.read();
+    let piece_theme = use_shared_state::<Settings>(cx)?.read().piece_theme.clone();
+    let pocket = use_shared_state::<Game>(cx)?.read().get_pocket(*player);
+    let size = board_size / 8;
+
+    cx.render(rsx! {
+        div {
+            class: "pocket-tray",
+            style: "width: {board_size}px; height: {size}px;",
+            for (piece, count) in pocket {
+                div {
+                    class: "pocket-piece",
+                    style: "width: {size}px; height: {size}px;",
+                    // dragging starts here and ends with the drop on the board
+                    onmousedown: move |event| {
+                        event.stop_propagation();
+                        on_pick.call(piece);
+                    },
+                    img {
+                        src: "{get_piece_image_file(&piece_theme, piece)}",
+                        class: "promotion-image",
+                        width: "{size}",
+                        height: "{size}",
+                    }
+                    span { class: "pocket-count", "{count}" }
+                }
+            }
+        }
+    })
+}
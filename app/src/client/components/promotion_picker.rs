@@ -0,0 +1,67 @@
+use chess::{Color, Move, Piece, Position};
+use dioxus::prelude::*;
+
+use super::super::{
+    components::board::{get_piece_image_file, to_point},
+    shared_states::{BoardSize, Perspective, Settings},
+};
+
+#[component]
+pub(crate) fn PromotionPicker<'a>(
+    cx: Scope<'a>,
+    mv: Move,
+    player: Color,
+    on_select: EventHandler<'a, Piece>,
+    on_cancel: EventHandler<'a>,
+) -> Element<'a> {
+    let board_size = **use_shared_state::<BoardSize>(cx)?.read();
+    let perspective = **use_shared_state::<Perspective>(cx)?.read();
+    let piece_theme = use_shared_state::<Settings>(cx)?.read().piece_theme.clone();
+    let size = board_size / 8;
+    // stack the choices from the promotion square towards the middle of the board
+    let choices = Piece::get_promotion_pieces(*player)
+        .into_iter()
+        .enumerate()
+        .map(|(i, piece)| {
+            let y = match player {
+                Color::White => mv.to.y - i,
+                Color::Black => mv.to.y + i,
+            };
+            (
+                piece,
+                to_point(board_size, perspective, &Position::new(mv.to.x, y)),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    cx.render(rsx! {
+        div {
+            class: "promotion-overlay",
+            onmousedown: move |event| {
+                event.stop_propagation();
+                on_cancel.call(());
+            },
+            for (piece, top_left) in choices {
+                div {
+                    class: "promotion-choice",
+                    style: "
+                        left: {top_left.x}px;
+                        top: {top_left.y}px;
+                        width: {size}px;
+                        height: {size}px;
+                    ",
+                    onmousedown: move |event| {
+                        event.stop_propagation();
+                        on_select.call(piece);
+                    },
+                    img {
+                        src: "{get_piece_image_file(&piece_theme, piece)}",
+                        class: "promotion-image",
+                        width: "{size}",
+                        height: "{size}",
+                    }
+                }
+            }
+        }
+    })
+}
@@ -1,34 +1,127 @@
-use chess::Game;
+use chess::{Game, MoveTime, Notation};
 use dioxus::prelude::*;
 
 #[component]
 pub(crate) fn RoundList(cx: Scope) -> Element {
-    let game = use_shared_state::<Game>(cx)?.read();
+    let game_state = use_shared_state::<Game>(cx)?;
+    let game = game_state.read();
     let current_round = game.get_current_round();
+    let current_path = game.get_current_path().to_vec();
+    let in_variation = game.is_in_variation();
+    let comment = game.get_annotation().comment.clone();
+    let move_class = |path: Option<&Vec<usize>>| {
+        if path == Some(&current_path) {
+            "round-move current-move"
+        } else {
+            "round-move"
+        }
+    };
+    let rounds = game
+        .get_rounds_info(Notation::Figurine)
+        .into_iter()
+        .map(|info| {
+            let white_time = display_time_spent(info.white_time);
+            let black_time = display_time_spent(info.black_time);
+            let white_class = move_class(info.white_path.as_ref());
+            let black_class = move_class(info.black_path.as_ref());
+            let variations: Vec<_> = info
+                .variations
+                .into_iter()
+                .map(|variation| {
+                    let moves: Vec<_> = variation
+                        .moves
+                        .into_iter()
+                        .map(|mv| {
+                            let class = move_class(Some(&mv.path));
+                            (mv.text, Some(mv.path), class)
+                        })
+                        .collect();
+                    (variation.depth, moves)
+                })
+                .collect();
+            (
+                info.number,
+                (info.white_string, info.white_path, white_time, white_class),
+                (info.black_string, info.black_path, black_time, black_class),
+                variations,
+            )
+        });
+    let go_to_move = move |path: &Option<Vec<usize>>| {
+        if let Some(path) = path {
+            game_state.write().go_to_move(path).ok();
+        }
+    };
 
     cx.render(rsx! {
         p { "Moves:" }
+        if in_variation {
+            rsx! {
+                div { class: "variation-buttons",
+                    button { class: "button",
+                        onclick: move |_| { game_state.write().promote_variation().ok(); },
+                        "Promote"
+                    }
+                    button { class: "button",
+                        onclick: move |_| { game_state.write().delete_variation().ok(); },
+                        "Delete"
+                    }
+                }
+            }
+        }
         div { class: "rounds-container",
             table { class: "place-content-center",
-                for (i, info) in game.get_rounds_info().into_iter().enumerate() {
+                for (i, (number, white, black, variations)) in rounds.enumerate() {
                     tr {
-                        class: if i + 1 == current_round {
+                        class: if i + 1 == current_round && !in_variation {
                             "mb-4 bg-gray-600/75"
                         } else {
                             "mb-4"
                         },
                         td {
-                            "{i + 1}."
+                            "{number}."
                         }
-                        td {
-                            "{info.white_string}"
+                        for (san, path, time, class) in [white, black] {
+                            td { class: class,
+                                onclick: move |_| go_to_move(&path),
+                                "{san}"
+                                span { class: "move-time", "{time}" }
+                            }
                         }
-                        td {
-                            "{info.black_string}"
+                    }
+                    for (depth, moves) in variations {
+                        tr {
+                            td { class: "variation",
+                                colspan: 3,
+                                padding_left: "{depth}rem",
+                                for (text, path, class) in moves {
+                                    span { class: class,
+                                        onclick: move |_| go_to_move(&path),
+                                        "{text}"
+                                    }
+                                }
+                            }
                         }
                     }
                 }
             }
         }
+        if !comment.is_empty() {
+            rsx! {
+                p { class: "move-comment", "{comment}" }
+            }
+        }
     })
 }
+
+/// The time spent on a move, e.g. `7s` or `1:05`, or nothing if it is unknown.
+fn display_time_spent(time: Option<MoveTime>) -> String {
+    let Some(time) = time else {
+        return String::new();
+    };
+    let total_secs = time.time_spent.as_secs();
+    if total_secs < 60 {
+        format!("{total_secs}s")
+    } else {
+        format!("{}:{:02}", total_secs / 60, total_secs % 60)
+    }
+}
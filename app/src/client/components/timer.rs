@@ -41,7 +41,8 @@ fn use_timer_future(cx: Scope, white_time: &UseState<String>, black_time: &UseSt
                     Color::White => white_time,
                     Color::Black => black_time,
                 };
-                loop {
+                // stop counting once the game ends, e.g. by resignation
+                while !game.read().game_over() {
                     let active_time = game.read().get_active_time();
                     let sleep_time = active_time.subsec_micros();
                     sleep(Duration::from_micros(sleep_time as u64)).await;
@@ -0,0 +1,19 @@
+use rfd::{FileDialog, MessageDialog, MessageLevel};
+
+/// Asks where to save `contents`, suggesting `file_name`, and tells the user if saving fails.
+pub(crate) fn save_file(file_name: &str, contents: &str) {
+    let Some(path) = FileDialog::new().set_file_name(file_name).save_file() else {
+        return;
+    };
+    match std::fs::write(&path, contents) {
+        Ok(()) => log::info!("Saved {}", path.display()),
+        Err(err) => {
+            log::error!("Error saving {}: {err:?}", path.display());
+            MessageDialog::new()
+                .set_level(MessageLevel::Error)
+                .set_title("Could not save the file")
+                .set_description(&format!("{}: {err}", path.display()))
+                .show();
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use wasm_bindgen::{JsCast, UnwrapThrowExt};
+use web_sys::HtmlElement;
+
+pub(crate) fn save_file(file_name: &str, contents: &str) {
+    let document = web_sys::window().unwrap_throw().document().unwrap_throw();
+    let link = document
+        .create_element("a")
+        .unwrap_throw()
+        .dyn_into::<HtmlElement>()
+        .unwrap_throw();
+    let href = format!(
+        "data:text/plain;charset=utf-8,{}",
+        js_sys::encode_uri_component(contents)
+    );
+    link.set_attribute("href", &href).unwrap_throw();
+    link.set_attribute("download", file_name).unwrap_throw();
+    link.click();
+}
@@ -1,32 +1,55 @@
-use async_std::channel::Receiver;
-use chess::{Game, Move};
+use async_std::channel::{unbounded, Receiver, Sender};
+use chess::{Color, Game};
 use dioxus::prelude::*;
 use futures_util::{
     join,
     stream::{SplitSink, SplitStream},
     SinkExt, StreamExt,
 };
+use once_cell::sync::Lazy;
 use tokio_tungstenite_wasm::{connect, Message, Message::Text, Result, WebSocketStream};
 use url::Url;
 
-use super::shared_states::GameId;
+use super::shared_states::{Disconnected, GameId};
+use crate::common::game_message::GameMessage;
 
 type WriteStream = SplitSink<WebSocketStream, Message>;
 type ReadStream = SplitStream<WebSocketStream>;
+type Channel = (Sender<GameMessage>, Receiver<GameMessage>);
+
+// Channel for sending messages to the socket to be sent to a remote player
+static MESSAGE_CHANNEL: Lazy<Channel> = Lazy::new(unbounded);
+
+pub(super) fn send_game_message(message: GameMessage) {
+    spawn(async move {
+        if let Err(e) = MESSAGE_CHANNEL.0.send(message).await {
+            log::error!("Failed to send game message: {e}")
+        }
+    });
+}
 
 pub(super) async fn create_game_socket(
     game: UseSharedState<Game>,
     game_id: UseSharedState<GameId>,
-    rx: &Receiver<Move>,
+    disconnected: UseSharedState<Disconnected>,
+    remote_player: Option<Color>,
 ) {
-    if let Some(game_id) = game_id.with(|id| **id) {
-        match connect_to_socket(game_id).await {
-            Ok((write, read)) => {
-                join!(read_from_socket(read, &game), write_to_socket(rx, write));
-            }
-            Err(err) => log::error!("Error connecting game socket: {err:?}"),
-        };
-    }
+    let (Some(game_id), Some(remote_player)) = (game_id.with(|id| **id), remote_player) else {
+        return;
+    };
+    match connect_to_socket(game_id).await {
+        Ok((write, read)) => {
+            **disconnected.write() = false;
+            join!(
+                read_from_socket(read, &game, &disconnected, remote_player),
+                write_to_socket(write)
+            );
+        }
+        Err(err) => {
+            log::error!("Error connecting game socket: {err:?}");
+            **disconnected.write() = true;
+        }
+    };
 }
 
 async fn connect_to_socket(game_id: u32) -> anyhow::Result<(WriteStream, ReadStream)> {
@@ -34,30 +57,75 @@ async fn connect_to_socket(game_id: u32) -> anyhow::Result<(WriteStream, ReadStr
     Ok(connect(Url::parse(&url)?).await?.split())
 }
 
-async fn send_move(mv: &Move, socket: &mut WriteStream) -> anyhow::Result<()> {
-    log::info!("Sending move {mv}");
-    Ok(socket.send(Text(serde_json::to_string(mv)?)).await?)
+async fn send_message(message: &GameMessage, socket: &mut WriteStream) -> anyhow::Result<()> {
+    log::info!("Sending {message:?}");
+    Ok(socket.send(Text(serde_json::to_string(message)?)).await?)
 }
 
-async fn write_to_socket(rx: &Receiver<Move>, mut socket: WriteStream) {
-    while let Ok(mv) = rx.recv().await {
-        if let Err(err) = send_move(&mv, &mut socket).await {
-            log::error!("Error sending move: {err:?}");
+async fn write_to_socket(mut socket: WriteStream) {
+    while let Ok(message) = MESSAGE_CHANNEL.1.recv().await {
+        if let Err(err) = send_message(&message, &mut socket).await {
+            log::error!("Error sending message: {err:?}");
         }
     }
 }
 
-fn handle_message(message: Result<Message>, game: &UseSharedState<Game>) -> anyhow::Result<()> {
-    let mv = serde_json::from_str::<Move>(&message?.into_text()?)?;
-    log::info!("Got move {mv}");
-    game.write().move_piece(mv.from, mv.to)?;
+/// The player a message says it acts for, which has to be the remote player.
+fn check_sender(player: Color, remote_player: Color) -> anyhow::Result<Color> {
+    anyhow::ensure!(
+        player == remote_player,
+        "the remote player cannot act for {player:?}"
+    );
+    Ok(player)
+}
+
+fn handle_message(
+    message: Result<Message>,
+    game: &UseSharedState<Game>,
+    remote_player: Color,
+) -> anyhow::Result<()> {
+    let message = serde_json::from_str::<GameMessage>(&message?.into_text()?)?;
+    log::info!("Got {message:?}");
+    let mut game = game.write();
+    match message {
+        GameMessage::Move(mv) => {
+            // the opponent's moves continue the game, not the line being looked at
+            game.resume();
+            game.move_piece(mv)?
+        }
+        GameMessage::Resign(player) => game.resign(check_sender(player, remote_player)?)?,
+        GameMessage::OfferDraw(player) => game.offer_draw(check_sender(player, remote_player)?)?,
+        GameMessage::RespondToDraw(accept) => game.respond_to_draw(remote_player, accept)?,
+        GameMessage::ClaimDraw => game.claim_draw()?,
+        GameMessage::RequestTakeback(player) => {
+            game.request_takeback(check_sender(player, remote_player)?)?
+        }
+        GameMessage::RespondToTakeback(accept) => {
+            game.respond_to_takeback(remote_player, accept)?
+        }
+        GameMessage::OpponentLeft => {
+            if !game.game_over() {
+                game.abandon(remote_player)?
+            }
+        }
+    }
     Ok(())
 }
 
-async fn read_from_socket(mut stream: ReadStream, game: &UseSharedState<Game>) {
+async fn read_from_socket(
+    mut stream: ReadStream,
+    game: &UseSharedState<Game>,
+    disconnected: &UseSharedState<Disconnected>,
+    remote_player: Color,
+) {
     while let Some(message) = stream.next().await {
-        if let Err(err) = handle_message(message, game) {
-            log::error!("Error receiving move: {err:?}");
+        if let Err(err) = handle_message(message, game, remote_player) {
+            log::error!("Error receiving message: {err:?}");
         }
     }
+    // the server tells us when the remote player leaves, so here we lost the connection ourselves
+    if !game.read().game_over() {
+        log::warn!("Lost the connection to the game");
+        **disconnected.write() = true;
+    }
 }
@@ -1,5 +1,7 @@
 mod arrows;
+mod bot;
 mod components;
+mod download;
 mod game_socket;
 mod helpers;
 mod mouse_click;
@@ -9,6 +9,14 @@ pub(super) struct Analyze(pub(super) bool);
 #[derive(AutoDeref)]
 pub(super) struct BoardSize(pub(super) u32);
 
+/// How deep the built-in engine searches when it plays as a bot.
+#[derive(AutoDeref)]
+pub(super) struct BotLevel(pub(super) u8);
+
+/// Whether the connection to the remote game was lost before it ended.
+#[derive(AutoDeref)]
+pub(super) struct Disconnected(pub(super) bool);
+
 #[derive(AutoDeref)]
 pub(super) struct GameId(pub(super) Option<u32>);
 
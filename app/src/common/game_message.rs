@@ -0,0 +1,16 @@
+use chess::{Color, Move};
+use serde::{Deserialize, Serialize};
+
+/// What the two players of a remote game send each other, through the server.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum GameMessage {
+    Move(Move),
+    Resign(Color),
+    OfferDraw(Color),
+    RespondToDraw(bool),
+    ClaimDraw,
+    RequestTakeback(Color),
+    RespondToTakeback(bool),
+    /// Sent by the server when the other player's connection closed.
+    OpponentLeft,
+}
@@ -1,2 +1,3 @@
 pub mod args;
+pub(crate) mod game_message;
 pub(crate) mod theme;
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
-use axum::{extract::WebSocketUpgrade, response::Response};
+use axum::{
+    extract::{ws::Message, WebSocketUpgrade},
+    response::Response,
+};
 use futures::{SinkExt, StreamExt};
 use tokio::sync::Mutex;
 
-use crate::server::server_functions::games::*;
+use crate::{common::game_message::GameMessage, server::server_functions::games::*};
 
 pub async fn handler(game_id: u32, ws: WebSocketUpgrade) -> Response {
     ws.on_upgrade(move |socket| async move {
@@ -43,6 +46,14 @@ async fn close_socket(game_id: u32, send: WebSocketSender) {
     GAMES.write().await.remove(&game_id);
 }
 
+async fn notify_opponent_left(send: &WebSocketSender) {
+    let message =
+        serde_json::to_string(&GameMessage::OpponentLeft).expect("game messages serialize to JSON");
+    if let Err(err) = send.lock().await.send(Message::Text(message)).await {
+        log::error!("Error telling the other app its opponent left: {err:?}");
+    }
+}
+
 async fn game_exists(game_id: u32) -> bool {
     GAMES.read().await.contains_key(&game_id)
 }
@@ -63,6 +74,11 @@ async fn forward_messages(game_id: u32, send: WebSocketSender, recv: WebSocketRe
             break;
         }
     }
+    // tell the player still connected that their opponent left, unless that player already
+    // disconnected and removed the game
+    if game_exists(game_id).await {
+        notify_opponent_left(&send).await;
+    }
     // if we have been disconnected, clean up our connections
     close_socket(game_id, send).await;
 }
@@ -0,0 +1,24 @@
+use chess::Game;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn perft(c: &mut Criterion) {
+    let initial = Game::new();
+    c.bench_function("perft 3 initial", |b| {
+        b.iter(|| black_box(&initial).perft(3))
+    });
+    let kiwipete = Game::from_fen(KIWIPETE).unwrap();
+    c.bench_function("perft 2 kiwipete", |b| {
+        b.iter(|| black_box(&kiwipete).perft(2))
+    });
+}
+
+fn from_fen(c: &mut Criterion) {
+    c.bench_function("from_fen kiwipete", |b| {
+        b.iter(|| Game::from_fen(black_box(KIWIPETE)))
+    });
+}
+
+criterion_group!(benches, perft, from_fen);
+criterion_main!(benches);
@@ -0,0 +1,84 @@
+use crate::position::Position;
+
+/// The glyphs of the NAGs 1 to 6, which assess the move they follow.
+const MOVE_GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+/// The colors of board markup, written as their first letter in the PGN `%cal` and `%csl`
+/// commands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MarkupColor {
+    #[default]
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl MarkupColor {
+    pub const ALL: [Self; 4] = [Self::Green, Self::Red, Self::Yellow, Self::Blue];
+
+    pub(super) fn get_char(self) -> char {
+        match self {
+            Self::Green => 'G',
+            Self::Red => 'R',
+            Self::Yellow => 'Y',
+            Self::Blue => 'B',
+        }
+    }
+
+    pub(super) fn from_char(c: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.get_char() == c)
+    }
+}
+
+/// An arrow drawn on the board, e.g. to show a plan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkupArrow {
+    pub from: Position,
+    pub to: Position,
+    pub color: MarkupColor,
+}
+
+/// A highlighted square of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkupSquare {
+    pub position: Position,
+    pub color: MarkupColor,
+}
+
+/// What a position of the history is annotated with: a comment, NAGs and board markup.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Annotation {
+    pub comment: String,
+    /// Numeric Annotation Glyphs, e.g. 1 for a good move `!`.
+    pub nags: Vec<u8>,
+    pub arrows: Vec<MarkupArrow>,
+    pub squares: Vec<MarkupSquare>,
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// The glyph a move assessment NAG is shown with, e.g. `!?` for 5.
+    pub fn get_nag_glyph(nag: u8) -> Option<&'static str> {
+        MOVE_GLYPHS.get(usize::from(nag).checked_sub(1)?).copied()
+    }
+
+    /// The NAG of a move assessment glyph, e.g. 5 for `!?`.
+    pub fn get_glyph_nag(glyph: &str) -> Option<u8> {
+        MOVE_GLYPHS
+            .iter()
+            .position(|&move_glyph| move_glyph == glyph)
+            .map(|i| i as u8 + 1)
+    }
+
+    /// The glyphs of the move assessment NAGs, e.g. `!?`.
+    pub fn get_move_glyphs(&self) -> String {
+        self.nags
+            .iter()
+            .filter_map(|&nag| Self::get_nag_glyph(nag))
+            .collect()
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::OnceLock;
+
+use crate::{bitboard::Bitboard, color::Color, position::Position};
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+];
+const KING_STEPS: [(i8, i8); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+const ROOK_DIRECTIONS: [(i8, i8); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const BISHOP_DIRECTIONS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+// found by a random search over sparse candidates, one per square from a1 to h8
+#[rustfmt::skip]
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080_0040_0880_1020, 0x0840_0920_02c0_3000, 0x1900_2000_1040_0900, 0x0880_1000_0800_0480,
+    0x4200_1004_2008_0200, 0x8100_0201_0008_0400, 0x0200_0401_1088_6200, 0x0200_0080_4022_0411,
+    0x0404_8000_8440_0220, 0x0000_4010_0040_2000, 0x0086_0010_8122_0440, 0x0408_8008_0010_0280,
+    0x000a_0012_0104_0820, 0x8848_8002_0084_0080, 0x4001_0001_0004_0200, 0x0442_0001_0210_5084,
+    0x9080_0100_2080_4100, 0x0040_4040_0020_1009, 0x0000_8080_1000_2009, 0x2200_0900_21d0_0100,
+    0x0008_0080_0804_0080, 0x0004_0040_0201_0040, 0x0011_0400_0801_5042, 0x0000_0a00_0176_8104,
+    0x0000_8000_8020_4009, 0x2010_0041_4000_2001, 0x9800_2002_8010_0080, 0x1000_1000_8008_0080,
+    0x0442_000a_0004_9020, 0x2100_0400_8002_0080, 0x0800_1204_0090_0148, 0x0010_040a_0012_8541,
+    0x2800_8040_0080_0030, 0x1010_0020_0040_0041, 0x4000_2000_1100_4100, 0x0610_0084_1080_0800,
+    0x0400_8024_0280_0800, 0xc100_0200_8080_0400, 0x0002_0008_0200_0401, 0x0182_0858_8200_0401,
+    0x0220_2040_0080_8000, 0x2860_1000_4002_4022, 0x0001_0020_0411_0040, 0x9910_1042_000a_0020,
+    0x0004_0800_0400_8080, 0x0010_0400_0200_8080, 0x2012_0048_8102_0004, 0x8300_8424_4482_0011,
+    0x0088_4038_8201_0200, 0x0820_4000_8021_0100, 0x0110_9100_40a0_0300, 0x0801_1002_8008_0480,
+    0x0242_0090_0820_0600, 0x1002_0004_8950_0200, 0x0040_8002_0001_0080, 0x0091_8000_4100_0080,
+    0x0000_2093_0048_8001, 0x04c1_0024_1482_4001, 0x0200_2000_0b00_1041, 0x7000_1000_0420_0901,
+    0x8002_0020_0410_0802, 0x3001_0002_084c_0007, 0x0888_2218_0081_3004, 0x4000_0028_4084_0112,
+];
+#[rustfmt::skip]
+const BISHOP_MAGICS: [u64; 64] = [
+    0xa010_0411_0800_3100, 0x0060_8202_0a00_2900, 0x6810_0106_1920_0000, 0x0828_1a05_2000_0408,
+    0x0001_1040_0100_0400, 0x0018_9010_0804_8400, 0x0004_0a02_1024_5280, 0x0002_0021_0808_a402,
+    0x9140_0484_1082_1200, 0x0800_0910_1082_0041, 0x2050_4804_8322_02c0, 0x0100_0914_0108_1000,
+    0x8021_0111_4000_0012, 0x0810_0208_0445_0400, 0x208b_0542_1090_08a2, 0x0080_084a_0804_0204,
+    0x0040_e2a8_0811_244c, 0x2505_0220_0800_8108, 0x0430_2201_0042_0040, 0x010a_0404_2022_0040,
+    0x1105_0002_9040_0000, 0x0093_0012_0082_2120, 0x4000_a620_4804_3004, 0x2801_2004_8a01_5004,
+    0x0060_9000_2a02_0814, 0x4404_2000_2408_00d0, 0x0110_2800_040a_4400, 0x1004_0800_8022_0040,
+    0x0001_0010_1100_4024, 0x0010_0440_0080_5040, 0x0914_0412_0082_0100, 0x0004_8210_1282_1480,
+    0x0024_0405_00c0_5021, 0x0088_6110_0208_0200, 0x0116_080a_0004_0020, 0x4000_0200_8008_0080,
+    0x2450_4501_4084_0040, 0x0000_8802_0148_4100, 0x0222_0204_0402_0092, 0x8081_1106_0000_2e00,
+    0x2842_1011_0500_0801, 0x1100_8090_0800_1025, 0x0002_0202_221c_0400, 0x0422_0140_2200_9020,
+    0x0210_0461_0210_0c00, 0xc004_0080_8202_9102, 0x00aa_4618_0110_1200, 0x0404_0800_8020_1108,
+    0x0205_4210_8c20_5002, 0x0410_5448_0410_0100, 0x0040_9108_4110_0000, 0x0400_2000_4202_1100,
+    0x0000_4204_8504_00c0, 0x0200_1004_10a4_2102, 0x1040_0208_0121_0102, 0x0805_0404_1042_0000,
+    0x2884_8041_3010_0200, 0x800c_2622_0124_2000, 0x1058_0001_9410_8800, 0x0014_2210_5442_0204,
+    0x0104_0000_12a0_2200, 0x0200_8810_0330_0100, 0x0140_4002_0284_0100, 0x0402_0208_0101_0201,
+];
+
+/// Maps each set of blockers on a slider's rays to an index into the sliding attack table.
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    /// Fills the attacks of `square` for every set of blockers into `sliding`.
+    fn new(square: usize, directions: &[(i8, i8)], number: u64, sliding: &mut Vec<u64>) -> Self {
+        let mask = get_blocker_mask(square, directions);
+        let magic = Self {
+            mask,
+            magic: number,
+            shift: 64 - mask.count_ones(),
+            offset: sliding.len(),
+        };
+        sliding.resize(sliding.len() + (1 << mask.count_ones()), 0);
+
+        // enumerate every subset of the mask
+        let mut blockers = 0u64;
+        loop {
+            let entry = &mut sliding[magic.get_index(Bitboard(blockers))];
+            let attacks = get_ray_attacks(square, directions, blockers);
+            debug_assert!(*entry == 0 || *entry == attacks, "bad magic for {square}");
+            *entry = attacks;
+            blockers = blockers.wrapping_sub(mask) & mask;
+            if blockers == 0 {
+                break;
+            }
+        }
+        magic
+    }
+
+    fn get_index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied.0 & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+/// Attack sets for every square, built once on first use.
+struct AttackTables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    rook_magics: Vec<Magic>,
+    bishop_magics: Vec<Magic>,
+    sliding: Vec<u64>,
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let mut sliding = Vec::new();
+        let mut get_magics = |directions: &[(i8, i8)], magics: &[u64; 64]| {
+            (0..64)
+                .map(|square| Magic::new(square, directions, magics[square], &mut sliding))
+                .collect()
+        };
+        let rook_magics = get_magics(&ROOK_DIRECTIONS, &ROOK_MAGICS);
+        let bishop_magics = get_magics(&BISHOP_DIRECTIONS, &BISHOP_MAGICS);
+
+        Self {
+            knight: std::array::from_fn(|square| get_step_attacks(square, &KNIGHT_STEPS)),
+            king: std::array::from_fn(|square| get_step_attacks(square, &KING_STEPS)),
+            pawn: [
+                std::array::from_fn(|square| get_step_attacks(square, &[(1, 1), (-1, 1)])),
+                std::array::from_fn(|square| get_step_attacks(square, &[(1, -1), (-1, -1)])),
+            ],
+            rook_magics,
+            bishop_magics,
+            sliding,
+        }
+    }
+}
+
+fn get_tables() -> &'static AttackTables {
+    static TABLES: OnceLock<AttackTables> = OnceLock::new();
+    TABLES.get_or_init(AttackTables::new)
+}
+
+pub(super) fn get_knight_attacks(at: &Position) -> Bitboard {
+    Bitboard(get_tables().knight[at.get_index()])
+}
+
+pub(super) fn get_king_attacks(at: &Position) -> Bitboard {
+    Bitboard(get_tables().king[at.get_index()])
+}
+
+/// The squares a pawn of `player` on `at` attacks.
+pub(super) fn get_pawn_attacks(at: &Position, player: Color) -> Bitboard {
+    Bitboard(get_tables().pawn[player as usize][at.get_index()])
+}
+
+pub(super) fn get_rook_attacks(at: &Position, occupied: Bitboard) -> Bitboard {
+    let tables = get_tables();
+    Bitboard(tables.sliding[tables.rook_magics[at.get_index()].get_index(occupied)])
+}
+
+pub(super) fn get_bishop_attacks(at: &Position, occupied: Bitboard) -> Bitboard {
+    let tables = get_tables();
+    Bitboard(tables.sliding[tables.bishop_magics[at.get_index()].get_index(occupied)])
+}
+
+pub(super) fn get_queen_attacks(at: &Position, occupied: Bitboard) -> Bitboard {
+    get_rook_attacks(at, occupied) | get_bishop_attacks(at, occupied)
+}
+
+fn get_offset_square(square: usize, (dx, dy): (i8, i8)) -> Option<usize> {
+    let x = (square % 8).checked_add_signed(dx as isize)?;
+    let y = (square / 8).checked_add_signed(dy as isize)?;
+    (x < 8 && y < 8).then_some(y * 8 + x)
+}
+
+fn get_step_attacks(square: usize, steps: &[(i8, i8)]) -> u64 {
+    steps
+        .iter()
+        .filter_map(|&step| get_offset_square(square, step))
+        .fold(0, |attacks, to| attacks | 1 << to)
+}
+
+/// Walks each ray from `square` up to and including the first occupied square.
+fn get_ray_attacks(square: usize, directions: &[(i8, i8)], occupied: u64) -> u64 {
+    let mut attacks = 0;
+    for &direction in directions {
+        let mut current = square;
+        while let Some(to) = get_offset_square(current, direction) {
+            attacks |= 1 << to;
+            if occupied & 1 << to != 0 {
+                break;
+            }
+            current = to;
+        }
+    }
+    attacks
+}
+
+/// The squares whose occupancy changes the attacks from `square`: its rays without the edges.
+fn get_blocker_mask(square: usize, directions: &[(i8, i8)]) -> u64 {
+    let mut mask = 0;
+    for &direction in directions {
+        let mut current = square;
+        while let Some(to) = get_offset_square(current, direction) {
+            if get_offset_square(to, direction).is_none() {
+                break;
+            }
+            mask |= 1 << to;
+            current = to;
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_attacks_match_ray_walk() {
+        let mut seed = 1u64;
+        let mut random = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        for square in 0..64 {
+            let at = Position::from_index(square);
+            for _ in 0..50 {
+                let occupied = random() & random();
+                assert_eq!(
+                    get_rook_attacks(&at, Bitboard(occupied)).0,
+                    get_ray_attacks(square, &ROOK_DIRECTIONS, occupied)
+                );
+                assert_eq!(
+                    get_bishop_attacks(&at, Bitboard(occupied)).0,
+                    get_ray_attacks(square, &BISHOP_DIRECTIONS, occupied)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! The crate's engine as a UCI engine on stdin and stdout, to load it into a chess GUI or play
+//! matches against other engines.
+
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use chess::{ChessError, Color, Engine, Game, Move, Score, SearchInfo, SearchLimits};
+
+const NAME: &str = "Oxide Chess";
+const AUTHOR: &str = "OxideOps";
+const DEFAULT_HASH_SIZE: usize = 16;
+const MAX_HASH_SIZE: usize = 4096;
+/// How many more moves the remaining time is split over, without `movestogo`.
+const MOVES_TO_GO: u32 = 30;
+/// Time kept back for the GUI to receive the move.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// A search running on its own thread, which hands the engine back when it ends.
+struct RunningSearch {
+    handle: JoinHandle<(Engine, Option<Move>)>,
+    /// The best move is only reported when the GUI stops an infinite search.
+    infinite: bool,
+}
+
+struct Uci {
+    /// The engine, unless a search has it.
+    engine: Option<Engine>,
+    stop: Arc<AtomicBool>,
+    search: Option<RunningSearch>,
+    game: Game,
+}
+
+impl Uci {
+    fn new() -> Self {
+        let engine = Engine::with_hash_size(DEFAULT_HASH_SIZE);
+        Self {
+            stop: engine.get_stop_handle(),
+            engine: Some(engine),
+            search: None,
+            game: Game::new(),
+        }
+    }
+
+    /// Handles a command, returning false on `quit`.
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<_> = line.split_whitespace().collect();
+        let Some((&command, args)) = tokens.split_first() else {
+            return true;
+        };
+        match command {
+            "uci" => {
+                println!("id name {NAME}");
+                println!("id author {AUTHOR}");
+                println!(
+                    "option name Hash type spin default {DEFAULT_HASH_SIZE} min 1 max {MAX_HASH_SIZE}"
+                );
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => self.get_engine().clear(),
+            "setoption" => self.set_option(args),
+            "position" => match parse_position(args) {
+                Ok(game) => self.game = game,
+                Err(err) => println!("info string invalid position: {err}"),
+            },
+            "go" => self.go(args),
+            "stop" => self.stop_search(),
+            "quit" => {
+                self.stop_search();
+                return false;
+            }
+            _ => println!("info string unknown command: {command}"),
+        }
+        true
+    }
+
+    /// The engine, after stopping the search that has it.
+    fn get_engine(&mut self) -> &mut Engine {
+        self.stop_search();
+        self.engine
+            .as_mut()
+            .expect("the engine is back after the search")
+    }
+
+    fn set_option(&mut self, args: &[&str]) {
+        let value_index = args.iter().position(|&arg| arg == "value");
+        let name = args[..value_index.unwrap_or(args.len())]
+            .iter()
+            .skip_while(|&&arg| arg == "name")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let value = value_index.and_then(|i| args.get(i + 1));
+        match (name.as_str(), value) {
+            ("Hash", Some(value)) => match value.parse::<usize>() {
+                Ok(megabytes) => self
+                    .get_engine()
+                    .set_hash_size(megabytes.clamp(1, MAX_HASH_SIZE)),
+                Err(_) => println!("info string invalid hash size: {value}"),
+            },
+            _ => println!("info string unknown option: {name}"),
+        }
+    }
+
+    fn go(&mut self, args: &[&str]) {
+        let infinite = args.contains(&"infinite");
+        let limits = parse_go(args, self.game.get_current_player());
+        self.stop_search();
+        let mut engine = self
+            .engine
+            .take()
+            .expect("the engine is back after the search");
+        let game = self.game.clone();
+        self.stop.store(false, Ordering::Relaxed);
+        let handle = thread::spawn(move || {
+            let best_move = engine.search(&game, limits, print_info);
+            if !infinite {
+                print_best_move(best_move);
+            }
+            (engine, best_move)
+        });
+        self.search = Some(RunningSearch { handle, infinite });
+    }
+
+    /// Stops the running search, if any, and takes the engine back.
+    fn stop_search(&mut self) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        self.stop.store(true, Ordering::Relaxed);
+        let (engine, best_move) = search.handle.join().expect("the search does not panic");
+        if search.infinite {
+            print_best_move(best_move);
+        }
+        self.engine = Some(engine);
+    }
+}
+
+/// The game after `position startpos|fen <fen> [moves <move>...]`.
+fn parse_position(args: &[&str]) -> Result<Game, ChessError> {
+    let moves_index = args
+        .iter()
+        .position(|&arg| arg == "moves")
+        .unwrap_or(args.len());
+    let mut game = match args.first() {
+        Some(&"fen") => Game::from_fen(&args[1..moves_index].join(" "))?,
+        _ => Game::new(),
+    };
+    for &lan in args.iter().skip(moves_index + 1) {
+        let mv = Move::from_lan(lan).ok_or(ChessError::InvalidMove)?;
+        game.move_piece(mv)?;
+    }
+    Ok(game)
+}
+
+/// The limits of `go [depth <plies>] [nodes <n>] [movetime <ms>] [wtime <ms>] [btime <ms>]
+/// [winc <ms>] [binc <ms>] [movestogo <n>] [infinite]` for `player` to move.
+fn parse_go(args: &[&str], player: Color) -> SearchLimits {
+    let get_value = |name| {
+        args.iter()
+            .position(|&arg| arg == name)
+            .and_then(|i| args.get(i + 1)?.parse::<u64>().ok())
+    };
+    let (time, increment) = match player {
+        Color::White => ("wtime", "winc"),
+        Color::Black => ("btime", "binc"),
+    };
+    let clock_time = get_value(time).map(|time| {
+        get_move_time(
+            Duration::from_millis(time),
+            Duration::from_millis(get_value(increment).unwrap_or(0)),
+            get_value("movestogo").map(|moves| moves as u32),
+        )
+    });
+    SearchLimits {
+        depth: get_value("depth").map(|depth| depth.min(u8::MAX as u64) as u8),
+        nodes: get_value("nodes"),
+        time: get_value("movetime")
+            .map(Duration::from_millis)
+            .or(clock_time),
+    }
+}
+
+/// The time to spend on a move with `time` left on the clock.
+fn get_move_time(time: Duration, increment: Duration, moves_to_go: Option<u32>) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(MOVES_TO_GO).max(1);
+    (time / moves_to_go + increment / 2).min(time.saturating_sub(MOVE_OVERHEAD))
+}
+
+fn print_info(info: &SearchInfo) {
+    let score = match info.score {
+        Score::Centipawns(centipawns) => format!("cp {centipawns}"),
+        Score::Mate(moves) => format!("mate {moves}"),
+    };
+    let millis = info.time.as_millis();
+    let nps = info.nodes as u128 * 1000 / millis.max(1);
+    let pv: Vec<_> = info.pv.iter().map(Move::to_lan).collect();
+    println!(
+        "info depth {} score {score} nodes {} nps {nps} time {millis} pv {}",
+        info.depth,
+        info.nodes,
+        pv.join(" ")
+    );
+}
+
+fn print_best_move(best_move: Option<Move>) {
+    // a null move when the game is over
+    let best_move = best_move.map_or("0000".to_string(), |mv| mv.to_lan());
+    println!("bestmove {best_move}");
+}
+
+fn main() {
+    let mut uci = Uci::new();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !uci.handle_command(&line) {
+            return;
+        }
+    }
+    uci.stop_search();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_position() {
+        let game = parse_position(&["startpos", "moves", "e2e4", "e7e5", "g1f3"]).unwrap();
+        assert_eq!(
+            game.get_fen_str(),
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+        );
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 0 1";
+        let args: Vec<_> = fen.split(' ').chain(["moves", "e1g1"]).collect();
+        let game = parse_position(&[&["fen"], &args[..]].concat()).unwrap();
+        assert_eq!(game.get_fen_str(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+        assert!(parse_position(&["startpos", "moves", "e2e5"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_go() {
+        assert_eq!(
+            parse_go(&["depth", "5", "nodes", "1000"], Color::White),
+            SearchLimits {
+                depth: Some(5),
+                nodes: Some(1000),
+                time: None,
+            }
+        );
+        assert_eq!(
+            parse_go(&["movetime", "200"], Color::Black),
+            SearchLimits::time(Duration::from_millis(200))
+        );
+        // a share of the player's own clock and half the increment
+        let args = [
+            "wtime", "60000", "btime", "3000", "winc", "1000", "binc", "0",
+        ];
+        assert_eq!(
+            parse_go(&args, Color::White),
+            SearchLimits::time(Duration::from_millis(2500))
+        );
+        assert_eq!(
+            parse_go(&args, Color::Black),
+            SearchLimits::time(Duration::from_millis(100))
+        );
+        assert_eq!(
+            parse_go(&["infinite"], Color::White),
+            SearchLimits::default()
+        );
+    }
+}
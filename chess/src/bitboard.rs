@@ -0,0 +1,89 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+use crate::position::Position;
+
+/// A set of squares, one bit per square with a1 as bit 0 and h8 as bit 63.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(super) struct Bitboard(pub(super) u64);
+
+impl Bitboard {
+    pub(super) const BACK_RANKS: Self = Self(0xff00_0000_0000_00ff);
+    /// d4, e4, d5 and e5.
+    pub(super) const CENTER: Self = Self(0x0000_0018_1800_0000);
+    pub(super) const EMPTY: Self = Self(0);
+    pub(super) const LIGHT_SQUARES: Self = Self(0x55aa_55aa_55aa_55aa);
+
+    pub(super) fn from_position(position: &Position) -> Self {
+        Self(1 << position.get_index())
+    }
+
+    /// The squares on rank `y` from file `x1` to file `x2`, both included.
+    pub(super) fn from_rank_span(y: usize, x1: usize, x2: usize) -> Self {
+        (x1.min(x2)..=x1.max(x2)).fold(Self::EMPTY, |span, x| {
+            span | Self::from_position(&Position::new(x, y))
+        })
+    }
+
+    pub(super) fn contains(self, position: &Position) -> bool {
+        self.0 & (1 << position.get_index()) != 0
+    }
+
+    pub(super) fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub(super) fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// The lowest square in the set.
+    pub(super) fn first(self) -> Option<Position> {
+        (!self.is_empty()).then(|| Position::from_index(self.0.trailing_zeros() as usize))
+    }
+}
+
+impl Iterator for Bitboard {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.first()?;
+        self.0 &= self.0 - 1;
+        Some(position)
+    }
+}
+
+impl BitOr for Bitboard {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Bitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0
+    }
+}
+
+impl BitAnd for Bitboard {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitAndAssign for Bitboard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0
+    }
+}
+
+impl Not for Bitboard {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
@@ -1,59 +1,185 @@
-use std::{hash::Hash, slice::Iter};
+use std::hash::Hash;
 
-use crate::{color::Color, piece::Piece, position::Position};
+use crate::{
+    bitboard::Bitboard, color::Color, piece::Piece, position::Position, result::ChessError, zobrist,
+};
 
 const BOARD_SIZE: usize = 8;
 
 pub(super) type Square = Option<Piece>;
+type PieceConstructor = fn(Color) -> Piece;
 
+/// The pieces on the board, both square by square and as one bitboard per piece and color.
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub(super) struct Board([[Square; BOARD_SIZE]; BOARD_SIZE]);
+pub(super) struct Board {
+    squares: [Square; BOARD_SIZE * BOARD_SIZE],
+    pieces: [Bitboard; 12],
+    colors: [Bitboard; 2],
+    /// The Zobrist key of the piece placement, updated on every `set_piece`.
+    zobrist_key: u64,
+}
 
 impl Default for Board {
     fn default() -> Self {
-        let mut squares = [[None; BOARD_SIZE]; BOARD_SIZE];
+        Self::with_back_rank([
+            Piece::Rook,
+            Piece::Knight,
+            Piece::Bishop,
+            Piece::Queen,
+            Piece::King,
+            Piece::Bishop,
+            Piece::Knight,
+            Piece::Rook,
+        ])
+    }
+}
+
+impl Board {
+    fn empty() -> Self {
+        Self {
+            squares: [None; BOARD_SIZE * BOARD_SIZE],
+            pieces: [Bitboard::EMPTY; 12],
+            colors: [Bitboard::EMPTY; 2],
+            zobrist_key: 0,
+        }
+    }
 
-        // Initialize pawns
-        for i in 0..8 {
-            squares[1][i] = Some(Piece::Pawn(Color::White));
-            squares[6][i] = Some(Piece::Pawn(Color::Black));
+    /// Parses the piece placement field of a FEN string, along with the squares of the pieces
+    /// marked as promoted by a `~` after them, as in Crazyhouse.
+    pub(super) fn from_fen_str(placement: &str) -> Result<(Self, Bitboard), ChessError> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != BOARD_SIZE {
+            return Err(ChessError::FenRankCount(ranks.len()));
         }
 
-        // Initialize the other pieces
-        squares[0] = Self::get_back_rank(Color::White);
-        squares[BOARD_SIZE - 1] = Self::get_back_rank(Color::Black);
+        let mut board = Self::empty();
+        let mut promoted = Bitboard::EMPTY;
+        for (rank, y) in ranks.into_iter().zip((0..BOARD_SIZE).rev()) {
+            let mut x = 0;
+            let mut follows_piece = false;
+            for c in rank.chars() {
+                if let Some(empty_count) = c.to_digit(10).filter(|n| (1..=8).contains(n)) {
+                    x += empty_count as usize;
+                    follows_piece = false;
+                } else if c == '~' {
+                    if !follows_piece {
+                        return Err(ChessError::FenPiece(c));
+                    }
+                    promoted |= Bitboard::from_position(&Position::new(x - 1, y));
+                    follows_piece = false;
+                } else {
+                    let piece = Piece::from_fen_char(c).ok_or(ChessError::FenPiece(c))?;
+                    if x >= BOARD_SIZE {
+                        return Err(ChessError::FenRankLength(y + 1));
+                    }
+                    board.set_piece(&Position::new(x, y), Some(piece));
+                    x += 1;
+                    follows_piece = true;
+                }
+            }
+            if x != BOARD_SIZE {
+                return Err(ChessError::FenRankLength(y + 1));
+            }
+        }
 
-        Self(squares)
+        Ok((board, promoted))
     }
-}
 
-impl Board {
-    fn get_back_rank(player: Color) -> [Square; 8] {
-        [
-            Some(Piece::Rook(player)),
-            Some(Piece::Knight(player)),
-            Some(Piece::Bishop(player)),
-            Some(Piece::Queen(player)),
-            Some(Piece::King(player)),
-            Some(Piece::Bishop(player)),
-            Some(Piece::Knight(player)),
-            Some(Piece::Rook(player)),
-        ]
+    /// A start position with pawns on the second ranks and mirrored back ranks.
+    fn with_back_rank(back_rank: [PieceConstructor; BOARD_SIZE]) -> Self {
+        let mut board = Self::empty();
+        for (x, piece) in back_rank.into_iter().enumerate() {
+            board.set_piece(&Position::new(x, 0), Some(piece(Color::White)));
+            board.set_piece(&Position::new(x, 1), Some(Piece::Pawn(Color::White)));
+            board.set_piece(&Position::new(x, 6), Some(Piece::Pawn(Color::Black)));
+            board.set_piece(&Position::new(x, 7), Some(piece(Color::Black)));
+        }
+        board
+    }
+
+    /// The Chess960 start position with the given index, numbered as by Scharnagl.
+    pub(super) fn chess960(index: u16) -> Option<Self> {
+        const KNIGHTS: [(usize, usize); 10] = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+        if index >= 960 {
+            return None;
+        }
+        let mut index = index as usize;
+        let mut back_rank: [Option<PieceConstructor>; BOARD_SIZE] = [None; BOARD_SIZE];
+        // one bishop on the light b, d, f or h-file and one on the dark a, c, e or g-file
+        back_rank[index % 4 * 2 + 1] = Some(Piece::Bishop);
+        index /= 4;
+        back_rank[index % 4 * 2] = Some(Piece::Bishop);
+        index /= 4;
+        let mut place_on_empty_square = |n: usize, piece| {
+            let x = (0..BOARD_SIZE)
+                .filter(|&x| back_rank[x].is_none())
+                .nth(n)
+                .unwrap();
+            back_rank[x] = Some(piece);
+        };
+        place_on_empty_square(index % 6, Piece::Queen);
+        // the second knight goes on the n-th empty square after the first one is placed
+        let (first, second) = KNIGHTS[index / 6];
+        place_on_empty_square(first, Piece::Knight);
+        place_on_empty_square(second - 1, Piece::Knight);
+        // the king goes between the rooks on the last three empty squares
+        for piece in [Piece::Rook, Piece::King, Piece::Rook] {
+            place_on_empty_square(0, piece);
+        }
+        Some(Self::with_back_rank(back_rank.map(Option::unwrap)))
     }
 
     pub(super) fn get_piece(&self, at: &Position) -> Square {
-        self.0[at.y][at.x]
+        self.squares[at.get_index()]
     }
 
     pub(super) fn set_piece(&mut self, at: &Position, square: Square) {
-        self.0[at.y][at.x] = square;
+        let bit = Bitboard::from_position(at);
+        if let Some(old_piece) = self.squares[at.get_index()] {
+            self.pieces[old_piece.get_index()] &= !bit;
+            self.colors[old_piece.get_player() as usize] &= !bit;
+            self.zobrist_key ^= zobrist::get_piece_key(old_piece, at);
+        }
+        if let Some(piece) = square {
+            self.pieces[piece.get_index()] |= bit;
+            self.colors[piece.get_player() as usize] |= bit;
+            self.zobrist_key ^= zobrist::get_piece_key(piece, at);
+        }
+        self.squares[at.get_index()] = square;
     }
 
     pub(super) fn take_piece(&mut self, from: &Position) -> Square {
-        self.0[from.y][from.x].take()
+        let square = self.get_piece(from);
+        self.set_piece(from, None);
+        square
+    }
+
+    /// The squares holding `piece`.
+    pub(super) fn get_pieces(&self, piece: Piece) -> Bitboard {
+        self.pieces[piece.get_index()]
+    }
+
+    /// The squares holding any piece of `player`.
+    pub(super) fn get_occupied(&self, player: Color) -> Bitboard {
+        self.colors[player as usize]
+    }
+
+    pub(super) fn get_all_occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
     }
 
-    pub(super) fn get_rows(&self) -> Iter<[Square; BOARD_SIZE]> {
-        self.0.iter()
+    pub(super) fn get_zobrist_key(&self) -> u64 {
+        self.zobrist_key
     }
 }
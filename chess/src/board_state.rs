@@ -1,107 +1,360 @@
 use std::hash::Hash;
 
 use crate::{
+    attacks,
+    bitboard::Bitboard,
     board::{Board, Square},
-    castling_rights::CastlingRights,
+    castling_rights::{CastlingRights, CastlingSide},
     color::Color,
     displacement::Displacement,
+    game_status::GameStatus,
     moves::Move,
     piece::Piece,
+    pocket::Pockets,
     position::Position,
     result::{ChessError, ChessResult},
+    variant::Variant,
+    zobrist,
 };
 
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+const MAX_FEN_STR: usize = 87;
+
+#[derive(Clone, Copy, Default, Hash, PartialEq, Eq)]
 /// A struct encapsulating the state for the `Board`.
 pub(super) struct BoardState {
     pub(super) player: Color,
     board: Board,
     pub(super) castling_rights: CastlingRights,
     pub(super) en_passant_position: Option<Position>,
-    pub(super) white_king_position: Position,
-    pub(super) black_king_position: Position,
+    pub(super) variant: Variant,
+    /// How many times each player has given check, counted in Three-check only.
+    pub(super) checks_given: [u8; 2],
+    /// The pieces each player holds to drop, in Crazyhouse only.
+    pub(super) pockets: Pockets,
+    /// The pieces that promoted from pawns and go back into a pocket as pawns when captured,
+    /// tracked in Crazyhouse only.
+    pub(super) promoted: Bitboard,
 }
 
-impl Default for BoardState {
-    fn default() -> Self {
-        Self {
-            player: Color::default(),
-            board: Board::default(),
-            castling_rights: CastlingRights::default(),
-            en_passant_position: None,
-            white_king_position: Position::WHITE_KING,
-            black_king_position: Position::BLACK_KING,
+impl BoardState {
+    /// Builds a state from its parts, validating the kings and pawns on the board.
+    pub(super) fn new(
+        board: Board,
+        player: Color,
+        castling_rights: CastlingRights,
+        en_passant_position: Option<Position>,
+    ) -> Result<Self, ChessError> {
+        for player in [Color::White, Color::Black] {
+            if board.get_pieces(Piece::King(player)).count() != 1 {
+                return Err(ChessError::FenKingCount(player));
+            }
+        }
+
+        let pawns = board.get_pieces(Piece::Pawn(Color::White))
+            | board.get_pieces(Piece::Pawn(Color::Black));
+        if let Some(pawn) = (pawns & Bitboard::BACK_RANKS).first() {
+            return Err(ChessError::FenPawnOnBackRank(pawn.y + 1));
         }
+
+        Ok(Self {
+            player,
+            board,
+            castling_rights,
+            en_passant_position,
+            variant: Variant::default(),
+            checks_given: [0; 2],
+            pockets: Pockets::default(),
+            promoted: Bitboard::EMPTY,
+        })
+    }
+
+    /// The start position of Chess960 with the given index from 0 to 959, where 518 is the
+    /// standard setup.
+    pub(super) fn chess960(index: u16) -> Result<Self, ChessError> {
+        let board = Board::chess960(index).ok_or(ChessError::Chess960Index(index))?;
+        Ok(Self {
+            castling_rights: CastlingRights::chess960(&board),
+            board,
+            ..Default::default()
+        })
+    }
+
+    pub(super) fn is_chess960(&self) -> bool {
+        self.castling_rights.is_chess960()
+    }
+
+    /// The status of a game ended by the rules of its variant, e.g. by an exploded king.
+    pub(super) fn get_variant_status(&self) -> Option<GameStatus> {
+        self.variant.get_status(self)
+    }
+
+    fn has_only_kings(&self) -> bool {
+        let kings =
+            self.get_pieces(Piece::King(Color::White)) | self.get_pieces(Piece::King(Color::Black));
+        (self.get_all_occupied() & !kings).is_empty()
     }
-}
 
-impl BoardState {
     pub(super) fn has_insufficient_material(&self) -> bool {
-        let mut white_minors = 0;
-        let mut black_minors = 0;
-        let mut white_bishop_square_color = None;
-        let mut black_bishop_square_color = None;
-
-        for (y, row) in self.board.get_rows().enumerate() {
-            for (x, piece) in row.iter().enumerate() {
-                match piece {
-                    Some(Piece::Bishop(Color::White)) => {
-                        white_minors += 1;
-                        white_bishop_square_color = Some((x + y) % 2);
-                    }
-                    Some(Piece::Bishop(Color::Black)) => {
-                        black_minors += 1;
-                        black_bishop_square_color = Some((x + y) % 2);
-                    }
-                    Some(Piece::Knight(Color::White)) => {
-                        white_minors += 1;
-                    }
-                    Some(Piece::Knight(Color::Black)) => {
-                        black_minors += 1;
-                    }
-                    Some(Piece::King(..)) | None => (),
-                    _ => return false,
-                }
-            }
+        match self.variant {
+            // a lone king can still walk to the center
+            Variant::KingOfTheHill => return false,
+            // only kings cannot give check
+            Variant::ThreeCheck => return self.has_only_kings(),
+            // any piece in a pocket can be dropped next to the opponent's king
+            Variant::Crazyhouse => return self.has_only_kings() && self.pockets.is_empty(),
+            Variant::Standard | Variant::Atomic => (),
+        }
+        let pieces = |piece| self.board.get_pieces(piece);
+        let has_major_piece_or_pawn = [Color::White, Color::Black].into_iter().any(|player| {
+            !(pieces(Piece::Pawn(player))
+                | pieces(Piece::Rook(player))
+                | pieces(Piece::Queen(player)))
+            .is_empty()
+        });
+        if has_major_piece_or_pawn {
+            return false;
         }
 
-        match (white_minors, black_minors) {
+        let minors =
+            |player| (pieces(Piece::Knight(player)) | pieces(Piece::Bishop(player))).count();
+        let bishop_square_color = |player| {
+            pieces(Piece::Bishop(player))
+                .first()
+                .map(|at| (at.x + at.y) % 2)
+        };
+        match (minors(Color::White), minors(Color::Black)) {
             (0, 0) | (1, 0) | (0, 1) => true,
-            (1, 1) => {
+            // in Atomic, capturing the piece next to the opponent's king blows it up
+            (1, 1) if self.variant == Variant::Standard => {
+                let white_bishop_square_color = bishop_square_color(Color::White);
                 white_bishop_square_color.is_none()
-                    || white_bishop_square_color != black_bishop_square_color
+                    || white_bishop_square_color != bishop_square_color(Color::Black)
             }
             _ => false,
         }
     }
 
+    /// Whether `player` could still win with some series of legal moves. This ignores a few
+    /// contrived positions, e.g. a lone knight helped by nothing but an opposing queen.
+    pub(super) fn can_win(&self, player: Color) -> bool {
+        match self.variant {
+            Variant::KingOfTheHill => return true,
+            Variant::ThreeCheck => {
+                return !(self.get_occupied(player) & !self.get_pieces(Piece::King(player)))
+                    .is_empty()
+            }
+            // even a lone king may capture a piece to drop it later
+            Variant::Crazyhouse => return !(self.has_only_kings() && self.pockets.is_empty()),
+            Variant::Standard | Variant::Atomic => (),
+        }
+        let pieces = |piece| self.board.get_pieces(piece);
+        if !(pieces(Piece::Pawn(player))
+            | pieces(Piece::Rook(player))
+            | pieces(Piece::Queen(player)))
+        .is_empty()
+        {
+            return true;
+        }
+
+        let knights = pieces(Piece::Knight(player));
+        let bishops = pieces(Piece::Bishop(player));
+        let opponent_pieces = self.get_occupied(!player) & !pieces(Piece::King(!player));
+        let bishop_squares = if bishops.is_empty() {
+            Bitboard::EMPTY
+        } else if (bishops & !Bitboard::LIGHT_SQUARES).is_empty() {
+            Bitboard::LIGHT_SQUARES
+        } else if (bishops & Bitboard::LIGHT_SQUARES).is_empty() {
+            !Bitboard::LIGHT_SQUARES
+        } else {
+            // bishops on both colors
+            return true;
+        };
+        // mating needs an opposing piece to block the king, which bishops on our color never do
+        let opponent_blockers =
+            opponent_pieces & !(pieces(Piece::Bishop(!player)) & bishop_squares);
+        match knights.count() {
+            0 if bishops.is_empty() => false,
+            0 => !opponent_blockers.is_empty(),
+            1 if bishops.is_empty() => !opponent_pieces.is_empty(),
+            _ => true,
+        }
+    }
+
+    /// The piece placement, active color, castling and en passant fields of the FEN string. In
+    /// Crazyhouse, promoted pieces are marked with a `~` and the pockets follow the placement in
+    /// brackets.
+    pub(super) fn get_fen_str(&self) -> String {
+        let mut fen = String::with_capacity(MAX_FEN_STR);
+        for y in (0..8).rev() {
+            let mut empty_count = 0;
+            for x in 0..8 {
+                if let Some(piece) = self.get_piece(&Position::new(x, y)) {
+                    if empty_count > 0 {
+                        fen.push_str(&empty_count.to_string());
+                        empty_count = 0;
+                    }
+                    fen.push(piece.get_fen_char());
+                    if self.promoted.contains(&Position::new(x, y)) {
+                        fen.push('~');
+                    }
+                } else {
+                    empty_count += 1;
+                }
+            }
+            if empty_count > 0 {
+                fen.push_str(&empty_count.to_string());
+            }
+            if y > 0 {
+                fen.push('/');
+            }
+        }
+        if self.variant == Variant::Crazyhouse {
+            fen.push_str(&format!("[{}]", self.pockets.get_fen_str()));
+        }
+        fen.push_str(&format!(
+            " {} {} {}",
+            self.player.get_fen_char(),
+            self.castling_rights.get_fen_str(&self.board),
+            self.en_passant_position
+                .map_or("-".to_string(), |pos| pos.to_string()),
+        ));
+        if self.variant == Variant::ThreeCheck {
+            let [white, black] = self.checks_given.map(|checks| 3 - checks.min(3));
+            fen.push_str(&format!(" {white}+{black}"));
+        }
+        fen
+    }
+
     pub(super) fn get_piece(&self, at: &Position) -> Square {
         self.board.get_piece(at)
     }
 
-    fn can_promote_piece(&self, piece: Piece, at: &Position) -> bool {
+    pub(super) fn can_promote_piece(&self, piece: Piece, at: &Position) -> bool {
         piece.is_pawn()
             && ((self.player == Color::White && at.y == 7)
                 || (self.player == Color::Black && at.y == 0))
     }
 
     pub(super) fn move_piece(&mut self, mv: &Move) {
-        let mut piece = self.board.take_piece(&mv.from).unwrap();
-        if self.can_promote_piece(piece, &mv.to) {
-            piece = Piece::Queen(self.player)
+        if let Some(piece) = mv.drop {
+            self.pockets.remove(piece);
+            self.board.set_piece(&mv.to, Some(piece));
+            return self.update(mv);
         }
-        self.board
-            .set_piece(&Position::new(mv.to.x, mv.to.y), Some(piece));
+        let is_capture = self.is_capture(mv);
+        if self.variant == Variant::Crazyhouse {
+            self.update_pockets(mv);
+        }
+        if let Some(side) = self.get_castling_side(mv) {
+            self.castle(side);
+        } else {
+            let mut piece = self.board.take_piece(&mv.from).unwrap();
+            if self.can_promote_piece(piece, &mv.to) {
+                piece = mv
+                    .promotion
+                    .expect("a pawn reaching the last rank must have a promotion piece")
+            }
+            if piece == Piece::King(self.player) {
+                self.castling_rights.remove_castling_rights(self.player);
+            }
+            self.board.set_piece(&mv.to, Some(piece));
+        }
+        self.handle_capturing_en_passant(mv);
+        if is_capture && self.variant == Variant::Atomic {
+            self.explode(&mv.to);
+        }
+        self.update(mv)
+    }
+
+    /// The piece `mv` moves or drops.
+    pub(super) fn get_moving_piece(&self, mv: &Move) -> Square {
+        mv.drop.or_else(|| self.get_piece(&mv.from))
+    }
+
+    /// Puts the piece `mv` captures into the pocket of the player to move, as a pawn if it had
+    /// promoted, and moves the promoted mark along with the moving piece. Called before the move
+    /// is made.
+    fn update_pockets(&mut self, mv: &Move) {
+        if self.is_capture(mv) {
+            let at = match self.get_piece(&mv.to) {
+                Some(_) => mv.to,
+                // en passant
+                None => mv.to - Displacement::get_pawn_advance_vector(self.player),
+            };
+            let piece = if self.promoted.contains(&at) {
+                Piece::Pawn(self.player)
+            } else {
+                self.get_piece(&at)
+                    .expect("a capture must take a piece")
+                    .with_player(self.player)
+            };
+            self.pockets.add(piece);
+        }
+        let was_promoted = self.promoted.contains(&mv.from);
+        self.promoted &= !(Bitboard::from_position(&mv.from) | Bitboard::from_position(&mv.to));
+        if was_promoted || mv.promotion.is_some() {
+            self.promoted |= Bitboard::from_position(&mv.to);
+        }
+    }
+
+    /// Whether `mv` captures a piece. A Chess960 castling move goes to the square of the player's
+    /// own rook, which is not a capture.
+    pub(super) fn is_capture(&self, mv: &Move) -> bool {
+        let is_pawn = self
+            .get_piece(&mv.from)
+            .is_some_and(|piece| piece.is_pawn());
+        (self.get_piece(&mv.to).is_some() && self.get_castling_side(mv).is_none())
+            || (is_pawn && mv.from.x != mv.to.x)
+    }
 
-        // Update king's position if a king is moved
-        if piece == Piece::King(self.player) {
-            match self.player {
-                Color::White => self.white_king_position = mv.to,
-                Color::Black => self.black_king_position = mv.to,
+    /// Removes the capturing piece on `at` and every piece but pawns next to it.
+    fn explode(&mut self, at: &Position) {
+        self.board.set_piece(at, None);
+        for neighbour in attacks::get_king_attacks(at) {
+            if self
+                .get_piece(&neighbour)
+                .is_some_and(|piece| !piece.is_pawn())
+            {
+                self.board.set_piece(&neighbour, None);
             }
         }
+    }
 
-        self.update(mv)
+    /// The square a castling move to `side` goes to: the rook's square in Chess960, otherwise the
+    /// king's destination.
+    pub(super) fn get_castling_target(&self, side: CastlingSide) -> Option<Position> {
+        let rook = self.castling_rights.get_rook_position(self.player, side)?;
+        Some(if self.castling_rights.is_chess960() {
+            rook
+        } else {
+            side.get_destinations(self.player).0
+        })
+    }
+
+    /// The side `mv` castles to, if it is a castling move.
+    pub(super) fn get_castling_side(&self, mv: &Move) -> Option<CastlingSide> {
+        if self.get_piece(&mv.from) != Some(Piece::King(self.player)) {
+            return None;
+        }
+        CastlingSide::ALL
+            .into_iter()
+            .find(|&side| self.get_castling_target(side) == Some(mv.to))
+    }
+
+    fn castle(&mut self, side: CastlingSide) {
+        let king = self.get_king_position(self.player);
+        let rook = self
+            .castling_rights
+            .get_rook_position(self.player, side)
+            .expect("castling needs the right to castle");
+        let (king_destination, rook_destination) = side.get_destinations(self.player);
+        self.board.set_piece(&king, None);
+        self.board.set_piece(&rook, None);
+        self.board
+            .set_piece(&king_destination, Some(Piece::King(self.player)));
+        self.board
+            .set_piece(&rook_destination, Some(Piece::Rook(self.player)));
+        self.castling_rights.remove_castling_rights(self.player);
     }
 
     pub(super) fn is_in_bounds(at: &Position) -> ChessResult {
@@ -120,16 +373,99 @@ impl BoardState {
     }
 
     fn update(&mut self, mv: &Move) {
-        self.castling_rights
-            .handle_castling_the_rook(mv, &mut self.board, self.player);
         self.castling_rights.update_castling_rights(&self.board);
-        self.handle_capturing_en_passant(&mv.to);
         self.update_en_passant(mv);
         self.player = !self.player;
+        if self.variant == Variant::ThreeCheck && self.is_in_check() {
+            self.checks_given[!self.player as usize] += 1;
+        }
     }
 
-    pub(super) fn has_piece(&self, position: &Position) -> bool {
-        Self::is_in_bounds(position).is_ok() && self.board.get_piece(position).is_some()
+    pub(super) fn get_king_position(&self, player: Color) -> Position {
+        self.board
+            .get_pieces(Piece::King(player))
+            .first()
+            .expect("both kings must be on the board")
+    }
+
+    pub(super) fn get_pieces(&self, piece: Piece) -> Bitboard {
+        self.board.get_pieces(piece)
+    }
+
+    pub(super) fn get_occupied(&self, player: Color) -> Bitboard {
+        self.board.get_occupied(player)
+    }
+
+    pub(super) fn get_all_occupied(&self) -> Bitboard {
+        self.board.get_all_occupied()
+    }
+
+    /// Whether any piece of `attacker` attacks the square `at` when the sliding pieces are blocked
+    /// by `occupied`.
+    fn is_square_attacked(&self, at: &Position, attacker: Color, occupied: Bitboard) -> bool {
+        let pieces = |piece| self.get_pieces(piece);
+        let queens = pieces(Piece::Queen(attacker));
+        // a pawn attacks `at` from where a pawn of the other color on `at` could capture
+        let attackers = (attacks::get_pawn_attacks(at, !attacker) & pieces(Piece::Pawn(attacker)))
+            | (attacks::get_knight_attacks(at) & pieces(Piece::Knight(attacker)))
+            | (attacks::get_king_attacks(at) & pieces(Piece::King(attacker)))
+            | (attacks::get_rook_attacks(at, occupied) & (pieces(Piece::Rook(attacker)) | queens))
+            | (attacks::get_bishop_attacks(at, occupied)
+                & (pieces(Piece::Bishop(attacker)) | queens));
+        !attackers.is_empty()
+    }
+
+    /// The Zobrist key of the position, used for repetition detection and as a transposition key.
+    pub(super) fn get_zobrist_key(&self) -> u64 {
+        let mut key = self.board.get_zobrist_key() ^ self.castling_rights.get_zobrist_key();
+        if self.player == Color::Black {
+            key ^= zobrist::get_black_to_move_key();
+        }
+        if let Some(en_passant_position) = self.get_capturable_en_passant_position() {
+            key ^= zobrist::get_en_passant_key(&en_passant_position);
+        }
+        for player in [Color::White, Color::Black] {
+            key ^= zobrist::get_checks_given_key(player, self.checks_given[player as usize]);
+        }
+        key ^= self.pockets.get_zobrist_key();
+        for at in self.promoted {
+            key ^= zobrist::get_promoted_key(&at);
+        }
+        key
+    }
+
+    /// The en passant square, if a pawn of the player to move could capture onto it.
+    fn get_capturable_en_passant_position(&self) -> Option<Position> {
+        self.en_passant_position.filter(|at| {
+            !(attacks::get_pawn_attacks(at, !self.player)
+                & self.get_pieces(Piece::Pawn(self.player)))
+            .is_empty()
+        })
+    }
+
+    /// Whether the player to move is in check.
+    pub(super) fn is_in_check(&self) -> bool {
+        self.is_king_attacked(self.player)
+    }
+
+    /// Whether the king of `player` is on the board and attacked.
+    pub(super) fn is_king_attacked(&self, player: Color) -> bool {
+        self.get_pieces(Piece::King(player))
+            .first()
+            .is_some_and(|king| !self.is_safe_for_king(&king, player))
+    }
+
+    /// Whether the king of `player` would not be attacked after moving to `at`. In Atomic, a king
+    /// next to the opposing king cannot be captured, as the capture would blow up both.
+    pub(super) fn is_safe_for_king(&self, at: &Position, player: Color) -> bool {
+        if self.variant == Variant::Atomic
+            && !(attacks::get_king_attacks(at) & self.get_pieces(Piece::King(!player))).is_empty()
+        {
+            return true;
+        }
+        // the king must not block attacks on the squares it moves through
+        let occupied = self.get_all_occupied() & !self.get_pieces(Piece::King(player));
+        !self.is_square_attacked(at, !player, occupied)
     }
 
     pub(super) fn was_double_move(&self, mv: &Move) -> bool {
@@ -142,10 +478,12 @@ impl BoardState {
         false
     }
 
-    fn handle_capturing_en_passant(&mut self, to: &Position) {
-        if Some(*to) == self.en_passant_position {
+    fn handle_capturing_en_passant(&mut self, mv: &Move) {
+        if Some(mv.to) == self.en_passant_position
+            && self.get_piece(&mv.to) == Some(Piece::Pawn(self.player))
+        {
             self.board.set_piece(
-                &(*to - Displacement::get_pawn_advance_vector(self.player)),
+                &(mv.to - Displacement::get_pawn_advance_vector(self.player)),
                 None,
             );
         }
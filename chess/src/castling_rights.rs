@@ -1,148 +1,220 @@
 use crate::{
-    board::Board, color::Color, displacement::Displacement, moves::Move, piece::Piece,
-    position::Position,
+    board::Board, color::Color, piece::Piece, position::Position, result::ChessError, zobrist,
 };
 
-#[derive(Clone, Copy)]
-pub(super) enum CastlingRightsKind {
-    WhiteKingside,
-    WhiteQueenside,
-    BlackKingside,
-    BlackQueenside,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum CastlingSide {
+    Kingside,
+    Queenside,
 }
 
+impl CastlingSide {
+    pub(super) const ALL: [Self; 2] = [Self::Kingside, Self::Queenside];
+
+    /// Where the king and the rook end up after castling, whatever their start files.
+    pub(super) fn get_destinations(self, player: Color) -> (Position, Position) {
+        let y = get_back_rank(player);
+        match self {
+            Self::Kingside => (Position::new(6, y), Position::new(5, y)),
+            Self::Queenside => (Position::new(2, y), Position::new(3, y)),
+        }
+    }
+
+    /// The file of the rook castling to this side in standard chess.
+    fn get_standard_file(self) -> usize {
+        match self {
+            Self::Kingside => 7,
+            Self::Queenside => 0,
+        }
+    }
+
+    fn get_fen_char(self, player: Color) -> char {
+        match self {
+            Self::Kingside => Piece::King(player),
+            Self::Queenside => Piece::Queen(player),
+        }
+        .get_fen_char()
+    }
+}
+
+fn get_back_rank(player: Color) -> usize {
+    match player {
+        Color::White => 0,
+        Color::Black => 7,
+    }
+}
+
+/// The castling rights of both players, stored as the file of the rook each right castles with.
 #[derive(Clone, Copy, Hash, PartialEq, Eq)]
-pub(super) struct CastlingRights([bool; 4]);
+pub(super) struct CastlingRights {
+    rook_files: [Option<usize>; 4],
+    /// Whether castling is written as the king capturing its own rook, as Chess960 needs when the
+    /// king and rooks can start anywhere on the back rank.
+    chess960: bool,
+}
 
 impl Default for CastlingRights {
     fn default() -> Self {
-        Self([true, true, true, true])
+        Self {
+            rook_files: [Some(7), Some(0), Some(7), Some(0)],
+            chess960: false,
+        }
     }
 }
 
 impl CastlingRights {
-    pub(super) fn rook_positions() -> [(Position, Piece, CastlingRightsKind); 4] {
-        [
-            (
-                Position::WHITE_KINGSIDE_ROOK,
-                Piece::Rook(Color::White),
-                CastlingRightsKind::WhiteKingside,
-            ),
-            (
-                Position::WHITE_QUEENSIDE_ROOK,
-                Piece::Rook(Color::White),
-                CastlingRightsKind::WhiteQueenside,
-            ),
-            (
-                Position::BLACK_KINGSIDE_ROOK,
-                Piece::Rook(Color::Black),
-                CastlingRightsKind::BlackKingside,
-            ),
-            (
-                Position::BLACK_QUEENSIDE_ROOK,
-                Piece::Rook(Color::Black),
-                CastlingRightsKind::BlackQueenside,
-            ),
-        ]
-    }
-
-    pub(super) fn king_positions() -> [(Position, Piece, CastlingRightsKind, CastlingRightsKind); 2]
-    {
-        [
-            (
-                Position::WHITE_KING,
-                Piece::King(Color::White),
-                CastlingRightsKind::WhiteKingside,
-                CastlingRightsKind::WhiteQueenside,
-            ),
-            (
-                Position::BLACK_KING,
-                Piece::King(Color::Black),
-                CastlingRightsKind::BlackKingside,
-                CastlingRightsKind::BlackQueenside,
-            ),
-        ]
-    }
-
-    pub(super) fn get_castling_positions(player: Color) -> (Position, Position, Position) {
-        match player {
-            Color::White => (
-                Position::WHITE_KING,
-                Position::WHITE_KINGSIDE_ROOK,
-                Position::WHITE_QUEENSIDE_ROOK,
-            ),
-            Color::Black => (
-                Position::BLACK_KING,
-                Position::BLACK_KINGSIDE_ROOK,
-                Position::BLACK_QUEENSIDE_ROOK,
-            ),
+    fn get_index(player: Color, side: CastlingSide) -> usize {
+        player as usize * 2 + side as usize
+    }
+
+    /// Every right for the rooks on `board`, where the king starts between two rooks.
+    pub(super) fn chess960(board: &Board) -> Self {
+        let mut rights = Self {
+            rook_files: [None; 4],
+            chess960: true,
+        };
+        for player in [Color::White, Color::Black] {
+            for side in CastlingSide::ALL {
+                rights.rook_files[Self::get_index(player, side)] =
+                    Self::get_outermost_rook(board, player, side).map(|rook| rook.x);
+            }
         }
+        rights
     }
 
-    pub(super) fn get_castling_info(
-        player: Color,
-    ) -> (Position, CastlingRightsKind, CastlingRightsKind) {
-        match player {
-            Color::White => (
-                Position::WHITE_KING,
-                CastlingRightsKind::WhiteKingside,
-                CastlingRightsKind::WhiteQueenside,
-            ),
-            Color::Black => (
-                Position::BLACK_KING,
-                CastlingRightsKind::BlackKingside,
-                CastlingRightsKind::BlackQueenside,
-            ),
+    /// The rook furthest from the king on `side` of the king's back rank square.
+    fn get_outermost_rook(board: &Board, player: Color, side: CastlingSide) -> Option<Position> {
+        let king = Self::get_king_on_back_rank(board, player)?;
+        let mut rooks = board
+            .get_pieces(Piece::Rook(player))
+            .filter(|rook| rook.y == king.y);
+        match side {
+            CastlingSide::Kingside => rooks.filter(|rook| rook.x > king.x).last(),
+            CastlingSide::Queenside => rooks.find(|rook| rook.x < king.x),
         }
     }
 
+    fn get_king_on_back_rank(board: &Board, player: Color) -> Option<Position> {
+        board
+            .get_pieces(Piece::King(player))
+            .first()
+            .filter(|king| king.y == get_back_rank(player))
+    }
+
+    /// Where the rook castling to `side` starts, if `player` may still castle there.
+    pub(super) fn get_rook_position(&self, player: Color, side: CastlingSide) -> Option<Position> {
+        self.rook_files[Self::get_index(player, side)]
+            .map(|x| Position::new(x, get_back_rank(player)))
+    }
+
+    pub(super) fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// Drops the rights of rooks that are no longer on their start square.
     pub(super) fn update_castling_rights(&mut self, board: &Board) {
-        for (position, piece, rights) in CastlingRights::rook_positions() {
-            if board.get_piece(&position) != Some(piece) {
-                self.0[rights as usize] = false;
+        for player in [Color::White, Color::Black] {
+            for side in CastlingSide::ALL {
+                if self
+                    .get_rook_position(player, side)
+                    .is_some_and(|rook| board.get_piece(&rook) != Some(Piece::Rook(player)))
+                {
+                    self.rook_files[Self::get_index(player, side)] = None;
+                }
             }
         }
+    }
 
-        for (position, piece, kingside_rights, queenside_rights) in CastlingRights::king_positions()
-        {
-            if board.get_piece(&position) != Some(piece) {
-                self.0[kingside_rights as usize] = false;
-                self.0[queenside_rights as usize] = false;
-            }
+    /// Drops both rights of `player`, e.g. once their king has moved.
+    pub(super) fn remove_castling_rights(&mut self, player: Color) {
+        for side in CastlingSide::ALL {
+            self.rook_files[Self::get_index(player, side)] = None;
         }
     }
 
-    pub(super) fn handle_castling_the_rook(&self, mv: &Move, board: &mut Board, player: Color) {
-        let (king, kingside_rook, queenside_rook) = CastlingRights::get_castling_positions(player);
-
-        if mv.from == king {
-            if mv.to == king + Displacement::RIGHT * 2 {
-                let rook = board.take_piece(&kingside_rook);
-                board.set_piece(&(kingside_rook + Displacement::LEFT * 2), rook);
-            } else if mv.to == king + Displacement::LEFT * 2 {
-                let rook = board.take_piece(&queenside_rook);
-                board.set_piece(&(queenside_rook + Displacement::RIGHT * 3), rook);
+    /// Parses the castling field of a FEN, X-FEN or Shredder-FEN string. Rights the board makes
+    /// impossible, e.g. `K` without a rook right of the king, are dropped.
+    pub(super) fn from_fen_str(fen: &str, board: &Board) -> Result<Self, ChessError> {
+        let mut rights = Self {
+            rook_files: [None; 4],
+            chess960: false,
+        };
+        if fen == "-" {
+            return Ok(rights);
+        }
+        let mut seen = [false; 4];
+        for c in fen.chars() {
+            let player = if c.is_ascii_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let king = Self::get_king_on_back_rank(board, player);
+            let (side, rook) = match c.to_ascii_lowercase() {
+                'k' => (
+                    CastlingSide::Kingside,
+                    Self::get_outermost_rook(board, player, CastlingSide::Kingside),
+                ),
+                'q' => (
+                    CastlingSide::Queenside,
+                    Self::get_outermost_rook(board, player, CastlingSide::Queenside),
+                ),
+                file @ 'a'..='h' => {
+                    let x = file as usize - 'a' as usize;
+                    let side = match king {
+                        Some(king) if x < king.x => CastlingSide::Queenside,
+                        _ => CastlingSide::Kingside,
+                    };
+                    let rook = Position::new(x, get_back_rank(player));
+                    let has_rook = king.is_some_and(|king| king.x != x)
+                        && board.get_piece(&rook) == Some(Piece::Rook(player));
+                    (side, has_rook.then_some(rook))
+                }
+                _ => return Err(ChessError::FenCastlingRights(fen.to_string())),
+            };
+            let index = Self::get_index(player, side);
+            if seen[index] {
+                return Err(ChessError::FenCastlingRights(fen.to_string()));
             }
+            seen[index] = true;
+            rights.rook_files[index] = rook.map(|rook| rook.x);
         }
+
+        // anything but the king on the e-file and rooks in the corners needs Chess960 castling
+        rights.chess960 = [Color::White, Color::Black].into_iter().any(|player| {
+            let king_x = Self::get_king_on_back_rank(board, player).map(|king| king.x);
+            CastlingSide::ALL.into_iter().any(|side| {
+                rights.rook_files[Self::get_index(player, side)]
+                    .is_some_and(|x| x != side.get_standard_file() || king_x != Some(4))
+            })
+        });
+        Ok(rights)
     }
 
-    pub(super) fn has_castling_right(&self, right: CastlingRightsKind) -> bool {
-        self.0[right as usize]
+    pub(super) fn get_zobrist_key(&self) -> u64 {
+        (0..4)
+            .filter(|&right| self.rook_files[right].is_some())
+            .fold(0, |key, right| key ^ zobrist::get_castling_right_key(right))
     }
 
-    pub(super) fn get_fen_str(&self) -> String {
-        let mut fen = String::default();
-        if self.0[CastlingRightsKind::WhiteKingside as usize] {
-            fen.push(Piece::King(Color::White).get_fen_char())
-        }
-        if self.0[CastlingRightsKind::WhiteQueenside as usize] {
-            fen.push(Piece::Queen(Color::White).get_fen_char())
-        }
-        if self.0[CastlingRightsKind::BlackKingside as usize] {
-            fen.push(Piece::King(Color::Black).get_fen_char())
-        }
-        if self.0[CastlingRightsKind::BlackQueenside as usize] {
-            fen.push(Piece::Queen(Color::Black).get_fen_char())
+    /// The castling field in X-FEN: `KQkq` for the outermost rooks, the rook's file otherwise.
+    pub(super) fn get_fen_str(&self, board: &Board) -> String {
+        let mut fen = String::new();
+        for player in [Color::White, Color::Black] {
+            for side in CastlingSide::ALL {
+                let Some(rook) = self.get_rook_position(player, side) else {
+                    continue;
+                };
+                if Self::get_outermost_rook(board, player, side) == Some(rook) {
+                    fen.push(side.get_fen_char(player));
+                } else {
+                    let file = rook.get_file_char();
+                    fen.push(match player {
+                        Color::White => file.to_ascii_uppercase(),
+                        Color::Black => file,
+                    });
+                }
+            }
         }
         if fen.is_empty() {
             String::from('-')
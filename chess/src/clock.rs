@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+
+use web_time::{Duration, Instant};
+
+/// The source of time for the players' clocks.
+pub trait Clock: Send + Sync {
+    /// The time passed since a fixed point, e.g. when the clock was created. It never goes back.
+    fn now(&self) -> Duration;
+}
+
+/// Reads the time of the system.
+pub struct SystemClock {
+    created: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            created: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.created.elapsed()
+    }
+}
+
+/// A clock that only moves when told to, for tests and for replaying recorded timestamps. Clones
+/// share the same time, so a clone kept outside a game still controls the game's clock.
+#[derive(Clone, Default)]
+pub struct ManualClock {
+    time: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.time.lock().unwrap() += duration;
+    }
+
+    /// Moves the clock to `time`, which must not be earlier than the current time.
+    pub fn set(&self, time: Duration) {
+        let mut current_time = self.time.lock().unwrap();
+        assert!(time >= *current_time, "a clock cannot go back");
+        *current_time = time;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.time.lock().unwrap()
+    }
+}
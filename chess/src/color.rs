@@ -14,6 +14,14 @@ impl Color {
             Self::Black => 'b',
         }
     }
+
+    pub(super) fn from_fen_str(s: &str) -> Option<Self> {
+        match s {
+            "w" => Some(Self::White),
+            "b" => Some(Self::Black),
+            _ => None,
+        }
+    }
 }
 
 impl std::ops::Not for Color {
@@ -0,0 +1,532 @@
+use std::{
+    cmp::Reverse,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use web_time::{Duration, Instant};
+
+use crate::{
+    board_state::BoardState,
+    game::Game,
+    moves::Move,
+    piece::Piece,
+    transposition_table::{Bound, TranspositionTable, MATE_THRESHOLD},
+};
+
+const DEFAULT_HASH_SIZE: usize = 16;
+const MAX_DEPTH: u8 = 64;
+/// The deepest the search goes, with the extensions and the quiescence search.
+const MAX_PLY: usize = 128;
+/// The score of being mated at the root, to which the plies until the mate are added.
+const MATE: i32 = 30_000;
+const INFINITY: i32 = MATE + 1;
+const DRAW: i32 = 0;
+/// How many nodes to search between looks at the time.
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+/// The score of a position for the player to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    /// A mate in the given number of moves, negative if the player to move gets mated.
+    Mate(i32),
+}
+
+impl Score {
+    fn from_search_score(score: i32) -> Self {
+        if score >= MATE_THRESHOLD {
+            Self::Mate((MATE - score + 1) / 2)
+        } else if score <= -MATE_THRESHOLD {
+            Self::Mate(-(MATE + score) / 2)
+        } else {
+            Self::Centipawns(score)
+        }
+    }
+}
+
+/// How far the engine may search, which also sets how strong it plays. A search stops at the
+/// first limit it reaches, and goes on until it is stopped without any.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    /// The number of plies, not counting the quiescence search.
+    pub depth: Option<u8>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u8) -> Self {
+        Self {
+            depth: Some(depth),
+            ..Self::default()
+        }
+    }
+
+    pub fn nodes(nodes: u64) -> Self {
+        Self {
+            nodes: Some(nodes),
+            ..Self::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> Self {
+        Self {
+            time: Some(time),
+            ..Self::default()
+        }
+    }
+}
+
+/// What the search found at the end of an iteration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchInfo {
+    pub depth: u8,
+    pub score: Score,
+    pub nodes: u64,
+    pub time: Duration,
+    /// The principal variation, the line the engine expects, starting with its best move.
+    pub pv: Vec<Move>,
+}
+
+/// A chess engine: an alpha-beta search with iterative deepening, a quiescence search and a
+/// transposition table, over a piece-square table evaluation. It runs on the calling thread,
+/// so it works in wasm as well.
+pub struct Engine {
+    table: TranspositionTable,
+    stop: Arc<AtomicBool>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::with_hash_size(DEFAULT_HASH_SIZE)
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An engine with a transposition table of about `megabytes`.
+    pub fn with_hash_size(megabytes: usize) -> Self {
+        Self {
+            table: TranspositionTable::new(megabytes),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn set_hash_size(&mut self, megabytes: usize) {
+        self.table = TranspositionTable::new(megabytes);
+    }
+
+    /// Forgets the positions searched before, e.g. for a new game.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// A flag that stops the running search when set, e.g. from another thread. It is cleared
+    /// when the search returns.
+    pub fn get_stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// The best move found within `limits` for the player to move in the current position of
+    /// `game`, or `None` if the game has ended there.
+    pub fn get_best_move(&mut self, game: &Game, limits: SearchLimits) -> Option<Move> {
+        self.search(game, limits, |_| ())
+    }
+
+    /// Searches the current position of `game` deeper and deeper until it reaches `limits` or is
+    /// stopped, calling `on_info` after each completed depth. Returns the best move found.
+    pub fn search(
+        &mut self,
+        game: &Game,
+        limits: SearchLimits,
+        mut on_info: impl FnMut(&SearchInfo),
+    ) -> Option<Move> {
+        let state = *game.get_current_state();
+        let mut best_move = state.get_legal_moves().first().copied();
+        if best_move.is_some() {
+            let mut search = Search {
+                table: &mut self.table,
+                stop: &self.stop,
+                limits,
+                start: Instant::now(),
+                nodes: 0,
+                stopped: false,
+                keys: game.get_previous_position_keys(),
+                killers: [[None; 2]; MAX_PLY],
+                root_best: None,
+            };
+            let halfmove_clock = game.get_halfmove_clock();
+            for depth in 1..=limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH) {
+                search.root_best = None;
+                let score = search.negamax(&state, depth, 0, -INFINITY, INFINITY, halfmove_clock);
+                // a move that beat the previous best before the search stopped is still better
+                best_move = search.root_best.or(best_move);
+                if search.stopped {
+                    break;
+                }
+                on_info(&SearchInfo {
+                    depth,
+                    score: Score::from_search_score(score),
+                    nodes: search.nodes,
+                    time: search.start.elapsed(),
+                    pv: search.get_pv(&state, best_move.unwrap()),
+                });
+                // the next iteration would not finish in time
+                if score.abs() >= MATE_THRESHOLD
+                    || limits
+                        .time
+                        .is_some_and(|time| search.start.elapsed() * 2 > time)
+                {
+                    break;
+                }
+            }
+        }
+        self.stop.store(false, Ordering::Relaxed);
+        best_move
+    }
+}
+
+/// The state of a running search.
+struct Search<'a> {
+    table: &'a mut TranspositionTable,
+    stop: &'a AtomicBool,
+    limits: SearchLimits,
+    start: Instant,
+    nodes: u64,
+    stopped: bool,
+    /// The keys of the positions before the one being searched, in the game and in the search,
+    /// to find repetitions.
+    keys: Vec<u64>,
+    /// Quiet moves that caused a beta cutoff at each ply, tried early in sibling positions.
+    killers: [[Option<Move>; 2]; MAX_PLY],
+    /// The best move at the root in the current iteration.
+    root_best: Option<Move>,
+}
+
+impl Search<'_> {
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+                || (self.nodes.is_multiple_of(TIME_CHECK_INTERVAL)
+                    && self
+                        .limits
+                        .time
+                        .is_some_and(|time| self.start.elapsed() >= time));
+        }
+        self.stopped
+    }
+
+    /// Whether the position with `key` occurred before, since the last capture or pawn move.
+    fn is_repetition(&self, key: u64, halfmove_clock: u32) -> bool {
+        self.keys
+            .iter()
+            .rev()
+            .take(halfmove_clock as usize)
+            .any(|&previous| previous == key)
+    }
+
+    fn negamax(
+        &mut self,
+        state: &BoardState,
+        depth: u8,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        halfmove_clock: u32,
+    ) -> i32 {
+        if self.should_stop() {
+            return DRAW;
+        }
+        self.nodes += 1;
+        let key = state.get_zobrist_key();
+        if ply > 0
+            && (halfmove_clock >= 100
+                || self.is_repetition(key, halfmove_clock)
+                || state.has_insufficient_material())
+        {
+            return DRAW;
+        }
+        let entry = self.table.get(key).copied();
+        if ply > 0 {
+            if let Some(score) = entry.and_then(|entry| entry.get_cutoff(depth, alpha, beta, ply)) {
+                return score;
+            }
+        }
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(state, ply, alpha, beta);
+        }
+
+        let mut moves = state.get_legal_moves();
+        let in_check = state.is_in_check();
+        if moves.is_empty() {
+            return self.get_end_score(state, in_check, ply);
+        }
+        // look further into forcing lines
+        let depth = match in_check && ply < MAX_PLY / 2 {
+            true => depth + 1,
+            false => depth,
+        };
+        self.order_moves(
+            state,
+            &mut moves,
+            entry.and_then(|entry| entry.best_move),
+            ply,
+        );
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        self.keys.push(key);
+        for (i, mv) in moves.into_iter().enumerate() {
+            let mut next_state = *state;
+            next_state.move_piece(&mv);
+            let next_halfmove_clock = match is_irreversible(state, &mv) {
+                true => 0,
+                false => halfmove_clock + 1,
+            };
+            let search = |search: &mut Self, alpha: i32, beta: i32| {
+                -search.negamax(
+                    &next_state,
+                    depth - 1,
+                    ply + 1,
+                    -beta,
+                    -alpha,
+                    next_halfmove_clock,
+                )
+            };
+            // after the first move, a null window only proves the other moves are worse
+            let mut score = match i {
+                0 => search(self, alpha, beta),
+                _ => search(self, alpha, alpha + 1),
+            };
+            if i > 0 && score > alpha && score < beta {
+                score = search(self, alpha, beta);
+            }
+            if self.stopped {
+                break;
+            }
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if score > alpha {
+                    alpha = score;
+                    if ply == 0 {
+                        self.root_best = Some(mv);
+                    }
+                }
+                if alpha >= beta {
+                    if !state.is_capture(&mv) {
+                        self.add_killer(mv, ply);
+                    }
+                    break;
+                }
+            }
+        }
+        self.keys.pop();
+        if self.stopped {
+            return DRAW;
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.table
+            .store(key, depth, best_score, bound, best_move, ply);
+        best_score
+    }
+
+    /// Searches only captures and promotions, unless in check, until the position is quiet
+    /// enough to evaluate.
+    fn quiescence(&mut self, state: &BoardState, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        if self.should_stop() {
+            return DRAW;
+        }
+        self.nodes += 1;
+        let in_check = state.is_in_check();
+        let mut moves = state.get_legal_moves();
+        if moves.is_empty() {
+            return self.get_end_score(state, in_check, ply);
+        }
+        if ply >= MAX_PLY {
+            return state.evaluate();
+        }
+        if !in_check {
+            // the player may take the evaluation instead of capturing
+            let stand_pat = state.evaluate();
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            moves.retain(|mv| state.is_capture(mv) || mv.promotion.is_some());
+        }
+        self.order_moves(state, &mut moves, None, ply);
+        for mv in moves {
+            let mut next_state = *state;
+            next_state.move_piece(&mv);
+            let score = -self.quiescence(&next_state, ply + 1, -beta, -alpha);
+            if self.stopped {
+                return DRAW;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// The score of a position without legal moves: a loss by the variant's rules or checkmate,
+    /// or stalemate.
+    fn get_end_score(&self, state: &BoardState, in_check: bool, ply: usize) -> i32 {
+        // the player to move is the one who lost by the variant's rules
+        if in_check || state.get_variant_status().is_some() {
+            -MATE + ply as i32
+        } else {
+            DRAW
+        }
+    }
+
+    fn add_killer(&mut self, mv: Move, ply: usize) {
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0];
+            killers[0] = Some(mv);
+        }
+    }
+
+    /// Sorts `moves` so that the likely best come first: the move stored for the position, then
+    /// captures of the most valuable pieces by the least valuable ones, promotions and killers.
+    fn order_moves(
+        &self,
+        state: &BoardState,
+        moves: &mut [Move],
+        table_move: Option<Move>,
+        ply: usize,
+    ) {
+        let killers = self.killers[ply.min(MAX_PLY - 1)];
+        moves.sort_by_cached_key(|mv| {
+            Reverse(if Some(*mv) == table_move {
+                1_000_000
+            } else if state.is_capture(mv) {
+                // en passant takes a pawn from an empty square
+                let victim = state.get_piece(&mv.to).map_or(100, Piece::get_value);
+                let attacker = state.get_piece(&mv.from).map_or(0, Piece::get_value);
+                100_000 + victim * 10 - attacker
+            } else if let Some(piece) = mv.promotion {
+                90_000 + piece.get_value()
+            } else if let Some(i) = killers.iter().position(|&killer| killer == Some(*mv)) {
+                80_000 - i as i32
+            } else {
+                0
+            })
+        });
+    }
+
+    /// The line from `best_move` on, following the best moves stored in the table.
+    fn get_pv(&self, state: &BoardState, best_move: Move) -> Vec<Move> {
+        let mut pv = vec![best_move];
+        let mut state = *state;
+        state.move_piece(&best_move);
+        while pv.len() < MAX_PLY {
+            let Some(mv) = self
+                .table
+                .get(state.get_zobrist_key())
+                .and_then(|entry| entry.best_move)
+                .filter(|mv| state.get_legal_moves().contains(mv))
+            else {
+                break;
+            };
+            state.move_piece(&mv);
+            pv.push(mv);
+        }
+        pv
+    }
+}
+
+/// Whether `mv` resets the fifty-move count: a capture, or a pawn move or drop.
+fn is_irreversible(state: &BoardState, mv: &Move) -> bool {
+    state.is_capture(mv)
+        || state
+            .get_moving_piece(mv)
+            .is_some_and(|piece| piece.is_pawn())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_best_move(fen: &str, depth: u8) -> String {
+        let game = Game::from_fen(fen).unwrap();
+        Engine::new()
+            .get_best_move(&game, SearchLimits::depth(depth))
+            .unwrap()
+            .to_lan()
+    }
+
+    #[test]
+    fn test_finds_mate() {
+        // back rank mate
+        assert_eq!(
+            get_best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3),
+            "a1a8"
+        );
+        // smothered mate in 2: Qg8+ Rxg8 Nf7#
+        let game = Game::from_fen("r6k/6pp/7N/8/8/1Q6/8/6K1 w - - 0 1").unwrap();
+        let mut infos = vec![];
+        let best_move = Engine::new().search(&game, SearchLimits::depth(4), |info| {
+            infos.push(info.clone())
+        });
+        assert_eq!(best_move.unwrap().to_lan(), "b3g8");
+        let info = infos.last().unwrap();
+        assert_eq!(info.score, Score::Mate(2));
+        assert_eq!(info.pv.len(), 3);
+    }
+
+    #[test]
+    fn test_wins_material() {
+        // the knight forks king and rook
+        assert_eq!(get_best_move("r3k3/8/8/1N6/8/8/8/4K3 w - - 0 1", 4), "b5c7");
+        // the queen takes the hanging rook
+        assert_eq!(get_best_move("4k3/8/8/3r4/8/8/8/3QK3 w - - 0 1", 2), "d1d5");
+    }
+
+    #[test]
+    fn test_game_over() {
+        let game = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(
+            Engine::new().get_best_move(&game, SearchLimits::depth(2)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let game = Game::new();
+        let mut engine = Engine::with_hash_size(1);
+        let mut nodes = 0;
+        assert!(engine
+            .search(&game, SearchLimits::nodes(2000), |info| nodes = info.nodes)
+            .is_some());
+        assert!(nodes <= 2000);
+        assert!(engine
+            .get_best_move(&game, SearchLimits::time(Duration::from_millis(50)))
+            .is_some());
+        // a stopped search still has a move
+        engine.get_stop_handle().store(true, Ordering::Relaxed);
+        assert!(engine
+            .get_best_move(&game, SearchLimits::default())
+            .is_some());
+        assert!(!engine.get_stop_handle().load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{board_state::BoardState, color::Color, piece::Piece, position::Position};
+
+/// The game phase of the pieces on the board, from 24 with every piece to 0 with only kings and
+/// pawns.
+const MAX_PHASE: i32 = 24;
+/// The bonus for each check given in Three-check.
+const CHECK_BONUS: i32 = 150;
+
+// Piece-square tables from white's side, with a8 first, as the board is seen.
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+/// The king hides behind its pawns while there are pieces to attack it.
+#[rustfmt::skip]
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+/// The king walks to the center once the pieces are gone.
+#[rustfmt::skip]
+const KING_ENDGAME_TABLE: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+impl Piece {
+    /// The material value of the piece in centipawns.
+    pub(super) fn get_value(self) -> i32 {
+        match self {
+            Self::Pawn(..) => 100,
+            Self::Knight(..) => 320,
+            Self::Bishop(..) => 330,
+            Self::Rook(..) => 500,
+            Self::Queen(..) => 900,
+            Self::King(..) => 0,
+        }
+    }
+
+    /// How much the piece adds to the game phase.
+    fn get_phase(self) -> i32 {
+        match self {
+            Self::Knight(..) | Self::Bishop(..) => 1,
+            Self::Rook(..) => 2,
+            Self::Queen(..) => 4,
+            Self::Pawn(..) | Self::King(..) => 0,
+        }
+    }
+}
+
+/// The index of `at` in a piece-square table for a piece of `player`.
+fn get_table_index(at: &Position, player: Color) -> usize {
+    match player {
+        Color::White => (7 - at.y) * 8 + at.x,
+        Color::Black => at.y * 8 + at.x,
+    }
+}
+
+impl BoardState {
+    /// A static evaluation of the position in centipawns for the player to move, from material,
+    /// piece-square tables, pockets in Crazyhouse and checks given in Three-check.
+    pub(super) fn evaluate(&self) -> i32 {
+        let mut phase = 0;
+        // white's score minus black's
+        let mut score = 0;
+        let mut king_scores = [(0, 0); 2];
+        for player in [Color::White, Color::Black] {
+            let sign = match player {
+                Color::White => 1,
+                Color::Black => -1,
+            };
+            for piece in [
+                Piece::Pawn(player),
+                Piece::Knight(player),
+                Piece::Bishop(player),
+                Piece::Rook(player),
+                Piece::Queen(player),
+                Piece::King(player),
+            ] {
+                let table = match piece {
+                    Piece::Pawn(..) => &PAWN_TABLE,
+                    Piece::Knight(..) => &KNIGHT_TABLE,
+                    Piece::Bishop(..) => &BISHOP_TABLE,
+                    Piece::Rook(..) => &ROOK_TABLE,
+                    Piece::Queen(..) => &QUEEN_TABLE,
+                    Piece::King(..) => {
+                        for at in self.get_pieces(piece) {
+                            let i = get_table_index(&at, player);
+                            king_scores[player as usize] =
+                                (KING_MIDDLEGAME_TABLE[i], KING_ENDGAME_TABLE[i]);
+                        }
+                        continue;
+                    }
+                };
+                for at in self.get_pieces(piece) {
+                    phase += piece.get_phase();
+                    score += sign * (piece.get_value() + table[get_table_index(&at, player)]);
+                }
+            }
+            for (piece, count) in self.pockets.get_pieces(player) {
+                score += sign * piece.get_value() * count as i32;
+            }
+            score += sign * CHECK_BONUS * self.checks_given[player as usize] as i32;
+        }
+        // the king tables blend from the middlegame to the endgame as the pieces come off
+        let phase = phase.min(MAX_PHASE);
+        for (player, (middlegame, endgame)) in
+            [Color::White, Color::Black].into_iter().zip(king_scores)
+        {
+            let king_score = (middlegame * phase + endgame * (MAX_PHASE - phase)) / MAX_PHASE;
+            score += match player {
+                Color::White => king_score,
+                Color::Black => -king_score,
+            };
+        }
+        match self.player {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+
+    #[test]
+    fn test_evaluate() {
+        let evaluate = |fen| Game::from_fen(fen).unwrap().get_current_state().evaluate();
+        // the start position is balanced
+        assert_eq!(
+            evaluate("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            0
+        );
+        // an extra queen, for the player to move or against them
+        let white_up = evaluate("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        assert!(white_up > 800);
+        assert_eq!(evaluate("4k3/8/8/8/8/8/8/3QK3 b - - 0 1"), -white_up);
+    }
+}
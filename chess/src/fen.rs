@@ -0,0 +1,200 @@
+use std::str::FromStr;
+
+use crate::{
+    board::Board, board_state::BoardState, castling_rights::CastlingRights, color::Color,
+    pocket::Pockets, position::Position, result::ChessError,
+};
+
+/// The fields of a parsed FEN string.
+pub(super) struct Fen {
+    pub(super) state: BoardState,
+    pub(super) halfmove_clock: u32,
+    pub(super) fullmove_number: usize,
+}
+
+impl Fen {
+    fn parse_en_passant(field: &str, player: Color) -> Result<Option<Position>, ChessError> {
+        if field == "-" {
+            return Ok(None);
+        }
+        let position = field
+            .parse::<Position>()
+            .map_err(|_| ChessError::FenEnPassant(field.to_string()))?;
+        let expected_rank = match player {
+            Color::White => 5,
+            Color::Black => 2,
+        };
+        if position.y != expected_rank {
+            return Err(ChessError::FenEnPassant(field.to_string()));
+        }
+        Ok(Some(position))
+    }
+
+    /// Splits the Crazyhouse pockets off the piece placement, written either in brackets, e.g.
+    /// `RNBQKBNR[Qp]`, or as a ninth rank, e.g. `RNBQKBNR/Qp`.
+    fn split_pockets(placement: &str) -> Result<(&str, Pockets), ChessError> {
+        if let Some(rest) = placement.strip_suffix(']') {
+            let (placement, pockets) = rest
+                .split_once('[')
+                .ok_or_else(|| ChessError::FenPockets(placement.to_string()))?;
+            return Ok((placement, pockets.parse()?));
+        }
+        match placement.rsplit_once('/') {
+            Some((ranks, pockets)) if placement.matches('/').count() == 8 => {
+                Ok((ranks, pockets.parse()?))
+            }
+            _ => Ok((placement, Pockets::default())),
+        }
+    }
+
+    /// Reads the remaining checks field of Three-check, e.g. `3+2` after one check by black, into
+    /// the checks each player has given.
+    fn parse_remaining_checks(field: &str) -> Result<[u8; 2], ChessError> {
+        let error = || ChessError::FenRemainingChecks(field.to_string());
+        let (white, black) = field.split_once('+').ok_or_else(error)?;
+        let mut checks_given = [0; 2];
+        for (checks, remaining) in checks_given.iter_mut().zip([white, black]) {
+            let remaining: u8 = remaining
+                .parse()
+                .ok()
+                .filter(|&n| n <= 3)
+                .ok_or_else(error)?;
+            *checks = 3 - remaining;
+        }
+        Ok(checks_given)
+    }
+}
+
+impl FromStr for Fen {
+    type Err = ChessError;
+
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        let mut fields: Vec<&str> = fen.split_whitespace().collect();
+        let checks_given = match fields.len() {
+            7 => Self::parse_remaining_checks(fields.remove(4))?,
+            _ => [0; 2],
+        };
+        let [placement, active_color, castling, en_passant, halfmove_clock, fullmove_number] =
+            fields[..]
+        else {
+            return Err(ChessError::FenFieldCount(fields.len()));
+        };
+
+        let (placement, pockets) = Self::split_pockets(placement)?;
+        let (board, promoted) = Board::from_fen_str(placement)?;
+        let player = Color::from_fen_str(active_color)
+            .ok_or_else(|| ChessError::FenActiveColor(active_color.to_string()))?;
+        let castling_rights = CastlingRights::from_fen_str(castling, &board)?;
+        let en_passant_position = Self::parse_en_passant(en_passant, player)?;
+        let halfmove_clock = halfmove_clock
+            .parse()
+            .map_err(|_| ChessError::FenHalfmoveClock(halfmove_clock.to_string()))?;
+        let fullmove_number = fullmove_number
+            .parse()
+            .ok()
+            .filter(|&n| n > 0)
+            .ok_or_else(|| ChessError::FenFullmoveNumber(fullmove_number.to_string()))?;
+
+        let mut state = BoardState::new(board, player, castling_rights, en_passant_position)?;
+        state.checks_given = checks_given;
+        state.pockets = pockets;
+        state.promoted = promoted;
+        Ok(Self {
+            state,
+            halfmove_clock,
+            fullmove_number,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn test_fen_round_trip() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2R b K - 37 60",
+        ] {
+            assert_eq!(Game::from_fen(fen).unwrap().get_fen_str(), fen);
+        }
+    }
+
+    #[test]
+    fn test_fen_errors() {
+        let parse = |fen: &str| fen.parse::<Fen>().err().unwrap();
+        assert!(matches!(
+            parse("8/8/8/8/8/8/8/8 w - -"),
+            ChessError::FenFieldCount(4)
+        ));
+        assert!(matches!(
+            parse("8/8/8/8/8/8/8 w - - 0 1"),
+            ChessError::FenRankCount(7)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K4 w - - 0 1"),
+            ChessError::FenRankLength(1)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4X3 w - - 0 1"),
+            ChessError::FenPiece('X')
+        ));
+        assert!(matches!(
+            parse("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+            ChessError::FenKingCount(Color::Black)
+        ));
+        assert!(matches!(
+            parse("P3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            ChessError::FenPawnOnBackRank(8)
+        ));
+        assert!(matches!(
+            parse("~4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            ChessError::FenPiece('~')
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1"),
+            ChessError::FenPockets(..)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+            ChessError::FenActiveColor(..)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K3 w KK - 0 1"),
+            ChessError::FenCastlingRights(..)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K3 w - e3 0 1"),
+            ChessError::FenEnPassant(..)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K3 w - - -1 1"),
+            ChessError::FenHalfmoveClock(..)
+        ));
+        assert!(matches!(
+            parse("4k3/8/8/8/8/8/8/4K3 w - - 0 0"),
+            ChessError::FenFullmoveNumber(..)
+        ));
+        assert!(Game::from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1").is_ok());
+        // a halfmove clock past 255 is read and written back
+        let fen = "4k3/8/8/8/8/8/8/4K2R w - - 300 200";
+        assert_eq!(Game::from_fen(fen).unwrap().get_fen_str(), fen);
+        assert!(matches!(
+            Game::from_fen("4k2R/8/8/8/8/8/8/4K3 w - - 0 1")
+                .err()
+                .unwrap(),
+            ChessError::FenOpponentInCheck
+        ));
+    }
+
+    #[test]
+    fn test_fen_drops_impossible_castling_rights() {
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.get_fen_str(), "4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+    }
+}
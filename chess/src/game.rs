@@ -1,36 +1,51 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, slice, sync::Arc};
 
 use web_time::Duration;
 
 use crate::{
+    annotation::Annotation,
+    bitboard::Bitboard,
     board_state::BoardState,
-    castling_rights::{CastlingRights, CastlingRightsKind},
+    clock::{Clock, SystemClock},
     color::Color,
-    displacement::Displacement,
+    fen::Fen,
+    game_result::{GameResult, Outcome},
     game_status::{DrawKind, GameStatus},
-    history::History,
+    history::{History, Node},
+    move_time::MoveTime,
     moves::Move,
+    pgn::{self, Pgn, PgnMove},
     piece::Piece,
+    pocket::Pockets,
     position::Position,
     result::{ChessError, ChessResult},
-    round_info::RoundInfo,
-    timer::{Timer, DEFAULT_DURATION},
-    turn::Turn,
+    round_info::{RoundInfo, VariationInfo, VariationMove},
+    san::Notation,
+    time_control::{Bonus, TimeControl},
+    timer::Timer,
+    variant::Variant,
 };
 
-const MAX_FEN_STR: usize = 87;
-
 #[derive(Clone)]
 pub struct Game {
     valid_moves: HashSet<Move>,
     pub(super) status: GameStatus,
     history: History,
     timer: Timer,
+    /// The player who offered a draw.
+    draw_offer: Option<Color>,
+    /// The player who asked to take back their last move.
+    takeback_request: Option<Color>,
+    automatic_draws: bool,
+    /// The tags of an imported game, written back on export.
+    tags: Vec<(String, String)>,
 }
 
 impl Default for Game {
     fn default() -> Self {
-        Self::builder().build()
+        Self::builder()
+            .build()
+            .expect("the standard start position is valid")
     }
 }
 
@@ -39,38 +54,106 @@ impl Game {
         Self::default()
     }
 
-    fn builder() -> GameBuilder {
+    pub fn builder() -> GameBuilder {
         GameBuilder::new()
     }
 
     pub fn with_start_time(start_time: Duration) -> Self {
-        GameBuilder::new().start_time(start_time).build()
+        Self::builder()
+            .start_time(start_time)
+            .build()
+            .expect("the standard start position is valid")
+    }
+
+    pub fn with_time_control(time_control: TimeControl) -> Self {
+        Self::builder()
+            .time_control(time_control)
+            .build()
+            .expect("the standard start position is valid")
+    }
+
+    /// Sets up a game from the position described by a FEN string.
+    pub fn from_fen(fen: &str) -> Result<Self, ChessError> {
+        Self::builder().fen(fen)?.build()
     }
 
-    fn with_state(state: BoardState) -> Self {
-        Self::builder().state(state).build()
+    /// Sets up the Chess960 start position with the given index from 0 to 959.
+    pub fn chess960(index: u16) -> Result<Self, ChessError> {
+        Self::builder().chess960(index)?.build()
+    }
+
+    pub fn get_variant(&self) -> Variant {
+        self.history.get_initial_state().variant
     }
 
     pub fn is_replaying(&self) -> bool {
         self.history.is_replaying()
     }
 
+    /// Whether the current move is in a variation rather than in the mainline of the game.
+    pub fn is_in_variation(&self) -> bool {
+        self.history.is_in_variation()
+    }
+
+    /// The path of the current move in the history: the index of the move chosen among the
+    /// alternatives at every ply, 0 being the main continuation. Empty before the first move.
+    pub fn get_current_path(&self) -> &[usize] {
+        self.history.get_current_path()
+    }
+
+    /// The comment, NAGs and board markup of the current position.
+    pub fn get_annotation(&self) -> &Annotation {
+        self.history.get_annotation()
+    }
+
+    pub fn set_annotation(&mut self, annotation: Annotation) {
+        self.history.set_annotation(annotation);
+    }
+
     pub fn is_in_check(&self) -> bool {
         matches!(self.status, GameStatus::Check(..))
     }
 
+    /// Starts a new game of the same variant, time control and clock.
     pub fn reset(&mut self) {
-        *self = Self::new()
+        let mut builder = Self::builder()
+            .variant(self.get_variant())
+            .time_control(self.get_time_control().clone())
+            .automatic_draws(self.automatic_draws);
+        builder.clock = self.timer.get_clock();
+        *self = builder
+            .build()
+            .expect("the standard start position is valid")
+    }
+
+    /// Ends the game at threefold repetition and the fifty-move rule instead of letting a player
+    /// claim the draw, as casual games often do.
+    pub fn set_automatic_draws(&mut self, automatic_draws: bool) {
+        self.automatic_draws = automatic_draws;
     }
 
     pub fn get_piece(&self, position: &Position) -> Option<Piece> {
         self.get_current_state().get_piece(position)
     }
 
-    fn get_current_state(&self) -> &BoardState {
+    pub(super) fn get_current_state(&self) -> &BoardState {
         self.history.get_current_state()
     }
 
+    /// The Zobrist keys of the positions before the current one, to find repetitions.
+    pub(super) fn get_previous_position_keys(&self) -> Vec<u64> {
+        self.history.get_previous_keys()
+    }
+
+    /// The FEN halfmove clock of the current position.
+    pub(super) fn get_halfmove_clock(&self) -> u32 {
+        self.history.get_halfmove_clock()
+    }
+
+    pub(super) fn get_valid_moves(&self) -> &HashSet<Move> {
+        &self.valid_moves
+    }
+
     pub fn get_current_player(&self) -> Color {
         self.get_current_state().player
     }
@@ -79,32 +162,40 @@ impl Game {
         self.get_current_state().is_piece_some(at)
     }
 
-    pub(super) fn has_piece(&self, position: &Position) -> bool {
-        self.get_current_state().has_piece(position)
+    pub fn get_valid_destinations_for_piece(&self, position: &Position) -> Vec<Position> {
+        self.valid_moves
+            .iter()
+            .filter(|mv| mv.drop.is_none() && mv.from == *position)
+            .map(|mv| mv.to)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect()
     }
 
-    fn piece_can_snipe(&self, at: &Position) -> bool {
-        self.get_piece(at).map_or(false, |piece| piece.can_snipe())
+    /// The squares `piece` may be dropped onto from the pocket of the player to move.
+    pub fn get_valid_drop_squares(&self, piece: Piece) -> Vec<Position> {
+        self.valid_moves
+            .iter()
+            .filter(|mv| mv.drop == Some(piece))
+            .map(|mv| mv.to)
+            .collect()
     }
 
-    fn has_castling_right(&self, right: CastlingRightsKind) -> bool {
-        self.get_current_state()
-            .castling_rights
-            .has_castling_right(right)
+    /// The pieces `player` holds to drop in Crazyhouse, with how many of each.
+    pub fn get_pocket(&self, player: Color) -> Vec<(Piece, u8)> {
+        self.get_current_state().pockets.get_pieces(player)
     }
 
-    pub fn get_valid_destinations_for_piece(&self, position: &Position) -> Vec<Position> {
+    /// Whether moving from `from` to `to` is a valid move that needs a promotion piece.
+    pub fn is_promotion(&self, from: &Position, to: &Position) -> bool {
         self.valid_moves
             .iter()
-            .filter(|mv| mv.from == *position)
-            .map(|mv| mv.to)
-            .collect()
+            .any(|mv| mv.from == *from && mv.to == *to && mv.promotion.is_some())
     }
 
     fn navigate_history(&mut self, navigate: impl FnOnce(&mut History)) {
         navigate(&mut self.history);
         self.add_moves();
-        self.remove_self_checks();
     }
 
     pub fn go_back_a_move(&mut self) {
@@ -123,232 +214,416 @@ impl Game {
         self.navigate_history(|history| history.resume());
     }
 
-    pub fn move_piece(&mut self, from: Position, to: Position) -> ChessResult {
-        if let Some(piece) = self.get_piece(&from) {
-            let mv = Move::new(from, to);
+    /// Goes to the move at `path`, see `get_current_path`.
+    pub fn go_to_move(&mut self, path: &[usize]) -> ChessResult {
+        if !self.history.go_to(path) {
+            return Err(ChessError::NotInHistory);
+        }
+        self.add_moves();
+        Ok(())
+    }
+
+    /// Goes to the next alternative to the current move, if there is one.
+    pub fn next_variation(&mut self) {
+        self.navigate_history(|history| {
+            history.go_to_sibling(true);
+        });
+    }
+
+    /// Goes to the previous alternative to the current move, if there is one.
+    pub fn previous_variation(&mut self) {
+        self.navigate_history(|history| {
+            history.go_to_sibling(false);
+        });
+    }
+
+    /// Makes the variation holding the current move the main continuation of the line it
+    /// branches off. Promoting a variation of the mainline changes how the game went on.
+    pub fn promote_variation(&mut self) -> ChessResult {
+        if !self.history.promote_variation() {
+            return Err(ChessError::NotInVariation);
+        }
+        if let Some(turn) = self.history.get_real_turn() {
+            self.status = turn.status;
+        }
+        self.draw_offer = None;
+        if self.status.is_game_over() {
+            self.timer.stop();
+        } else {
+            self.timer.set_current_player(self.get_real_player());
+        }
+        Ok(())
+    }
+
+    /// Removes the variation holding the current move and goes back to where it branches off.
+    pub fn delete_variation(&mut self) -> ChessResult {
+        if !self.history.delete_variation() {
+            return Err(ChessError::NotInVariation);
+        }
+        self.add_moves();
+        Ok(())
+    }
+
+    /// Plays a move from the current position. At the end of the mainline this continues the
+    /// game, anywhere else the move starts or follows a variation that leaves the game as it is.
+    pub fn move_piece(&mut self, mv: Move) -> ChessResult {
+        if let Some(piece) = self.get_current_state().get_moving_piece(&mv) {
             self.is_move_valid(&mv)?;
             let mut next_state = *self.get_current_state();
             next_state.move_piece(&mv);
-            self.history.add_info(next_state, mv);
+            let continues_game = !self.is_replaying();
+            let is_new = self.history.add_info(next_state, mv);
 
             log::info!("{} : {}", piece, mv);
-            self.update();
+            if continues_game {
+                // an offer lapses once its player moves
+                if self.draw_offer == Some(piece.get_player()) {
+                    self.draw_offer = None;
+                }
+                self.takeback_request = None;
+                self.update();
+            } else {
+                self.add_moves();
+                if let Some(status) = self.get_current_status().filter(|_| is_new) {
+                    self.history.set_current_status(status);
+                }
+            }
         }
         Ok(())
     }
 
-    fn update(&mut self) {
-        self.add_moves();
-        self.remove_self_checks();
-        self.update_status();
-        self.history.update_status(self.status);
-        self.update_timer();
+    /// Ends the game with `player` resigning.
+    pub fn resign(&mut self, player: Color) -> ChessResult {
+        self.end_game(GameStatus::Resigned(player))
     }
 
-    fn update_timer(&mut self) {
+    /// Ends an imported game with its recorded result, unless its moves already ended it.
+    pub(super) fn set_recorded_result(&mut self, outcome: Outcome) -> ChessResult {
         if self.status.is_game_over() {
-            self.timer.stop();
-            return;
+            return Ok(());
         }
-        if !self.timer.is_active() {
-            self.timer.start()
-        }
-        self.timer.print();
-        self.timer.next_player();
+        self.end_game(GameStatus::Recorded(outcome))
     }
 
-    fn check_for_draw(&mut self) -> bool {
-        if self.history.get_fifty_move_count() == 50 {
-            self.status
-                .update(GameStatus::Draw(DrawKind::FiftyMoveRule));
-            return true;
+    /// Ends the game after `player` left it, e.g. by disconnecting from a remote game.
+    pub fn abandon(&mut self, player: Color) -> ChessResult {
+        self.end_game(GameStatus::Abandoned(player))
+    }
+
+    /// Offers a draw to the opponent of `player`. The offer stands until the opponent responds or
+    /// `player` moves.
+    pub fn offer_draw(&mut self, player: Color) -> ChessResult {
+        if self.status.is_game_over() {
+            return Err(ChessError::GameOver);
         }
-        if self.history.get_real_state_repetition_count() == 3 {
-            self.status.update(GameStatus::Draw(DrawKind::Repetition));
-            return true;
+        self.draw_offer = Some(player);
+        Ok(())
+    }
+
+    /// Lets `responder` accept or decline the draw their opponent offered.
+    pub fn respond_to_draw(&mut self, responder: Color, accept: bool) -> ChessResult {
+        match self.draw_offer {
+            None => return Err(ChessError::NoDrawOffer),
+            Some(player) if player == responder => return Err(ChessError::OwnDrawOffer),
+            Some(_) => self.draw_offer = None,
         }
-        if self.history.get_real_state().has_insufficient_material() {
-            self.status
-                .update(GameStatus::Draw(DrawKind::InsufficientMaterial));
-            return true;
+        if accept {
+            self.end_game(GameStatus::Draw(DrawKind::Agreement))?;
         }
-        false
+        Ok(())
     }
 
-    fn update_status(&mut self) {
-        if self.get_active_time().is_zero() {
-            self.status
-                .update(GameStatus::Timeout(self.get_real_player()));
-            return;
+    /// The player who has a draw offer pending.
+    pub fn get_draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// Takes back the last `n` moves of the game, with the variations replacing them. The clocks
+    /// go back to their times after the last move left, and the draw counts to its position. A
+    /// game that was over goes on again from there.
+    pub fn take_back(&mut self, n: usize) -> ChessResult {
+        if !self.history.take_back(n) {
+            return Err(ChessError::NoMoveToTakeBack);
         }
-        if self.check_for_draw() {
-            return;
+        let ply = self.history.get_mainline_path().len();
+        let start_time = self.get_time_control().get_periods()[0].time;
+        let times = match self.history.get_real_turn() {
+            Some(turn) => turn.time.map(|time| [time.white_time, time.black_time]),
+            None => Some([start_time; 2]),
         }
-        let king_is_under_attack = Self::is_king_under_attack(
-            &self
-                .history
-                .get_real_turn()
-                .unwrap_or(&Turn::default())
-                .board_state,
-        );
-        let valid_moves_is_empty = self.valid_moves.is_empty();
+        // moves read from PGN may have no clocks
+        .unwrap_or_else(|| self.timer.get_times_at_last_move());
+        let first_player = self.history.get_initial_state().player;
+        let mut moves = [0; 2];
+        moves[first_player as usize] = ply.div_ceil(2) as u32;
+        moves[!first_player as usize] = (ply / 2) as u32;
+        let game_over = self.status.is_game_over();
+        self.timer
+            .take_back(times, moves, self.get_real_player(), game_over);
+
+        self.draw_offer = None;
+        self.takeback_request = None;
+        self.status = GameStatus::default();
+        self.add_moves();
+        self.update_status();
+        Ok(())
+    }
 
-        if !king_is_under_attack && valid_moves_is_empty {
-            self.status.update(GameStatus::Draw(DrawKind::Stalemate))
-        } else if king_is_under_attack && valid_moves_is_empty {
-            self.status
-                .update(GameStatus::Checkmate(self.get_real_player()))
-        } else if king_is_under_attack {
-            self.status
-                .update(GameStatus::Check(self.get_real_player()))
-        } else if !self.history.turns.is_empty() {
-            self.status.update(GameStatus::Ongoing)
+    /// How many moves to take back to undo the last move of `player` and the opponent's reply to
+    /// it, if any. `None` if `player` has not moved yet.
+    pub fn get_takeback_length(&self, player: Color) -> Option<usize> {
+        let ply = self.history.get_mainline_path().len();
+        let n = if self.get_real_player() == player {
+            2
+        } else {
+            1
+        };
+        (n <= ply).then_some(n)
+    }
+
+    /// Asks the opponent of `player` to let them take back their last move. The request stands
+    /// until the opponent responds or makes a move.
+    pub fn request_takeback(&mut self, player: Color) -> ChessResult {
+        if self.status.is_game_over() {
+            return Err(ChessError::GameOver);
         }
+        if self.get_takeback_length(player).is_none() {
+            return Err(ChessError::NoMoveToTakeBack);
+        }
+        self.takeback_request = Some(player);
+        Ok(())
     }
 
-    fn is_attacking_king(&self) -> bool {
-        self.valid_moves
-            .iter()
-            .any(|mv| self.get_piece(&mv.to) == Some(Piece::King(!self.get_current_player())))
+    /// Lets `responder` accept or decline the takeback their opponent requested.
+    pub fn respond_to_takeback(&mut self, responder: Color, accept: bool) -> ChessResult {
+        let player = match self.takeback_request {
+            None => return Err(ChessError::NoTakebackRequest),
+            Some(player) if player == responder => return Err(ChessError::OwnTakebackRequest),
+            Some(player) => player,
+        };
+        self.takeback_request = None;
+        if accept {
+            let n = self
+                .get_takeback_length(player)
+                .ok_or(ChessError::NoMoveToTakeBack)?;
+            self.take_back(n)?;
+        }
+        Ok(())
     }
 
-    fn is_king_under_attack(board_state: &BoardState) -> bool {
-        let mut enemy_board = *board_state;
-        enemy_board.player = !enemy_board.player;
-        Self::with_state(enemy_board).is_attacking_king()
+    /// The player who has a takeback request pending.
+    pub fn get_takeback_request(&self) -> Option<Color> {
+        self.takeback_request
     }
 
-    fn moves_into_check(mut board_state: BoardState, mv: &Move) -> bool {
-        board_state.move_piece(mv);
-        Self::with_state(board_state).is_attacking_king()
+    fn end_game(&mut self, status: GameStatus) -> ChessResult {
+        if self.status.is_game_over() {
+            return Err(ChessError::GameOver);
+        }
+        self.status.update(status);
+        self.history.update_status(status);
+        self.draw_offer = None;
+        self.takeback_request = None;
+        self.timer.stop();
+        Ok(())
     }
 
-    fn remove_self_checks(&mut self) {
-        let current_state = *self.get_current_state();
-        self.valid_moves
-            .retain(|mv| !Self::moves_into_check(current_state, mv))
+    fn update(&mut self) {
+        self.add_moves();
+        self.update_status();
+        self.history.update_status(self.status);
+        let time_spent = self.update_timer();
+        let [white_time, black_time] = self.timer.get_times_at_last_move();
+        self.history.set_move_time(Some(MoveTime {
+            white_time,
+            black_time,
+            time_spent,
+        }));
     }
 
-    pub fn is_move_valid(&self, mv: &Move) -> ChessResult {
-        if self.get_active_time().is_zero() {
-            return Err(ChessError::Timeout);
+    /// Ends the clock of the move just made and returns how long it took.
+    fn update_timer(&mut self) -> Duration {
+        if self.status.is_game_over() {
+            return self.timer.stop().unwrap_or_default();
         }
-        if matches!(self.status, GameStatus::Draw(..)) {
-            return Err(ChessError::GameIsInDraw);
+        if !self.timer.is_active() {
+            self.timer.start()
         }
-        BoardState::is_in_bounds(&mv.from)?;
-        BoardState::is_in_bounds(&mv.to)?;
-        self.is_piece_some(&mv.from)?;
-        if self.valid_moves.contains(mv) {
-            Ok(())
+        self.timer.print();
+        self.timer.next_player()
+    }
+
+    /// Replaces the clocks recorded for the current move with `remaining` on the clock of the
+    /// player who made it, as read from a PGN `%clk` comment. The opponent keeps their last
+    /// recorded time and the time spent is worked out from the mover's previous time, not
+    /// counting delays.
+    pub(super) fn set_clock(&mut self, remaining: Duration) {
+        let line = self.history.get_current_line();
+        let Some(ply) = line.len().checked_sub(1) else {
+            return;
+        };
+        let mover = !self.get_current_player();
+        let start_time = self.get_time_control().get_periods()[0].time;
+        let get_time_before = |ply: Option<usize>, player| match ply {
+            Some(ply) => line[ply].time.map(|time| time.get_time(player)),
+            None => Some(start_time),
+        };
+        let moves = if self.history.get_initial_state().player == mover {
+            ply / 2 + 1
         } else {
-            Err(ChessError::InvalidMove)
-        }
+            ply.div_ceil(2)
+        } as u32;
+        let time_control = self.get_time_control();
+        let bonus = match time_control.get_bonus(moves - 1) {
+            Bonus::Increment(increment) => increment,
+            _ => Duration::ZERO,
+        } + time_control.get_time_added_after(moves);
+        let time_spent = get_time_before(ply.checked_sub(2), mover)
+            .map_or(Duration::ZERO, |time| {
+                (time + bonus).saturating_sub(remaining)
+            });
+        let opponent_time = get_time_before(ply.checked_sub(1), !mover).unwrap_or(start_time);
+        let (white_time, black_time) = match mover {
+            Color::White => (remaining, opponent_time),
+            Color::Black => (opponent_time, remaining),
+        };
+        self.history.set_move_time(Some(MoveTime {
+            white_time,
+            black_time,
+            time_spent,
+        }));
     }
 
-    fn add_pawn_advance_moves(&mut self, from: &Position) {
-        let v = Displacement::get_pawn_advance_vector(self.get_current_player());
-        let mut to = *from + v;
-        if BoardState::is_in_bounds(&to).is_ok() && self.get_piece(&to).is_none() {
-            self.valid_moves.insert(Move::new(*from, to));
-            to += v;
-            if self.can_double_move(from) && self.get_piece(&to).is_none() {
-                self.valid_moves.insert(Move::new(*from, to));
-            }
-        }
+    /// Forgets the clocks recorded for the current move, e.g. for a move read from PGN.
+    pub(super) fn clear_move_time(&mut self) {
+        self.history.set_move_time(None);
     }
 
-    fn can_double_move(&self, from: &Position) -> bool {
-        if let Some(Piece::Pawn(player)) = self.get_piece(from) {
-            return match player {
-                Color::White => from.y == 1,
-                Color::Black => from.y == 6,
-            };
+    /// The draw the players may claim after the move at `path`: threefold repetition or the
+    /// fifty-move rule.
+    fn get_claimable_draw(&self, path: &[usize]) -> Option<DrawKind> {
+        if self.history.get_repetition_count(path) >= 3 {
+            Some(DrawKind::Repetition)
+        } else if self.history.get_fifty_move_count(path) >= 50 {
+            Some(DrawKind::FiftyMoveRule)
+        } else {
+            None
         }
-        false
     }
 
-    fn add_pawn_capture_moves(&mut self, from: &Position) {
-        for &v in Displacement::get_pawn_capture_vectors(self.get_current_player()) {
-            let to = *from + v;
-            if BoardState::is_in_bounds(&to).is_ok() {
-                if let Some(piece) = self.get_piece(&to) {
-                    if piece.get_player() != self.get_current_player() {
-                        self.valid_moves.insert(Move::new(*from, to));
-                    }
-                }
-                if Some(to) == self.get_current_state().en_passant_position {
-                    self.valid_moves.insert(Move::new(*from, to));
-                }
-            }
+    pub fn can_claim_draw(&self) -> bool {
+        !self.status.is_game_over()
+            && self
+                .get_claimable_draw(&self.history.get_mainline_path())
+                .is_some()
+    }
+
+    /// Ends the game in a draw by threefold repetition or the fifty-move rule.
+    pub fn claim_draw(&mut self) -> ChessResult {
+        if self.status.is_game_over() {
+            return Err(ChessError::GameOver);
         }
+        let kind = self
+            .get_claimable_draw(&self.history.get_mainline_path())
+            .ok_or(ChessError::NoDrawToClaim)?;
+        self.end_game(GameStatus::Draw(kind))
     }
 
-    fn add_moves_for_piece(&mut self, from: &Position) {
-        if let Some(piece) = self.get_piece(from) {
-            if piece.get_player() == self.get_current_player() {
-                if piece.is_pawn() {
-                    self.add_pawn_advance_moves(from);
-                    self.add_pawn_capture_moves(from);
-                } else {
-                    for &v in piece.get_vectors() {
-                        let mut to = *from + v;
-                        while BoardState::is_in_bounds(&to).is_ok() {
-                            if let Some(piece) = self.get_piece(&to) {
-                                if piece.get_player() != self.get_current_player() {
-                                    self.valid_moves.insert(Move::new(*from, to));
-                                }
-                                break;
-                            }
-                            self.valid_moves.insert(Move::new(*from, to));
-                            if !self.piece_can_snipe(from) {
-                                break;
-                            }
-                            to += v;
-                        }
-                    }
-                }
-            }
+    /// The draw the rules end the game in at the current position.
+    fn get_automatic_draw(&self) -> Option<DrawKind> {
+        let path = self.history.get_current_path();
+        if self.history.get_repetition_count(path) >= 5 {
+            Some(DrawKind::FivefoldRepetition)
+        } else if self.history.get_fifty_move_count(path) >= 75 {
+            Some(DrawKind::SeventyFiveMoveRule)
+        } else if self.get_current_state().has_insufficient_material() {
+            Some(DrawKind::InsufficientMaterial)
+        } else if self.automatic_draws {
+            self.get_claimable_draw(path)
+        } else {
+            None
         }
     }
 
-    fn add_moves(&mut self) {
-        self.valid_moves.clear();
-        for y in 0..8 {
-            for x in 0..8 {
-                self.add_moves_for_piece(&Position::new(x, y))
-            }
+    /// The status of the game when the player to move has run out of time.
+    fn get_timeout_status(&self) -> GameStatus {
+        let player = self.get_real_player();
+        if self.history.get_real_state().can_win(!player) {
+            GameStatus::Timeout(player)
+        } else {
+            GameStatus::Draw(DrawKind::TimeoutVsInsufficientMaterial)
         }
-        self.add_castling_moves()
     }
 
-    fn add_castling_moves(&mut self) {
-        let (king_square, kingside, queenside) =
-            CastlingRights::get_castling_info(self.get_current_player());
+    /// The status at the current position, or `None` before the first move of a game that can go
+    /// on.
+    fn get_current_status(&self) -> Option<GameStatus> {
+        if !self.is_replaying() && self.get_active_time().is_zero() {
+            return Some(self.get_timeout_status());
+        }
+        let state = self.get_current_state();
+        if let Some(status) = state.get_variant_status() {
+            return Some(status);
+        }
+        let king_is_under_attack = state.is_in_check();
+        let valid_moves_is_empty = self.valid_moves.is_empty();
+
+        // checkmate takes precedence over the automatic draw rules
+        if king_is_under_attack && valid_moves_is_empty {
+            return Some(GameStatus::Checkmate(state.player));
+        }
+        if let Some(kind) = self.get_automatic_draw() {
+            return Some(GameStatus::Draw(kind));
+        }
+        if valid_moves_is_empty {
+            Some(GameStatus::Draw(DrawKind::Stalemate))
+        } else if king_is_under_attack {
+            Some(GameStatus::Check(state.player))
+        } else if !self.history.get_current_path().is_empty() {
+            Some(GameStatus::Ongoing)
+        } else {
+            None
+        }
+    }
 
-        if self.has_castling_right(kingside)
-            && !Self::moves_into_check(
-                *self.get_current_state(),
-                &Move::new(king_square, king_square + Displacement::RIGHT),
-            )
-            && !(1..=2).any(|i| self.has_piece(&(king_square + Displacement::RIGHT * i)))
-        {
-            self.valid_moves.insert(Move::new(
-                king_square,
-                king_square + Displacement::RIGHT * 2,
-            ));
+    fn update_status(&mut self) {
+        if let Some(status) = self.get_current_status() {
+            self.status.update(status);
         }
+    }
 
-        if self.has_castling_right(queenside)
-            && !Self::moves_into_check(
-                *self.get_current_state(),
-                &Move::new(king_square, king_square + Displacement::LEFT),
-            )
-            && !(1..=3).any(|i| self.has_piece(&(king_square + Displacement::LEFT * i)))
-        {
-            self.valid_moves
-                .insert(Move::new(king_square, king_square + Displacement::LEFT * 2));
+    /// Checks that `mv` can be played from the current position. Only moves that continue the
+    /// game need it to be still going.
+    pub fn is_move_valid(&self, mv: &Move) -> ChessResult {
+        if !self.is_replaying() {
+            if self.get_active_time().is_zero() {
+                return Err(ChessError::Timeout);
+            }
+            if matches!(self.status, GameStatus::Draw(..)) {
+                return Err(ChessError::GameIsInDraw);
+            }
+            if self.status.is_game_over() {
+                return Err(ChessError::GameOver);
+            }
         }
+        BoardState::is_in_bounds(&mv.from)?;
+        BoardState::is_in_bounds(&mv.to)?;
+        if mv.drop.is_none() {
+            self.is_piece_some(&mv.from)?;
+        }
+        if self.valid_moves.contains(mv) {
+            Ok(())
+        } else {
+            Err(ChessError::InvalidMove)
+        }
+    }
+
+    fn add_moves(&mut self) {
+        self.valid_moves = self
+            .get_current_state()
+            .get_legal_moves()
+            .into_iter()
+            .collect();
     }
 
     pub fn get_active_time(&self) -> Duration {
@@ -363,6 +638,20 @@ impl Game {
         self.timer.get_time(player)
     }
 
+    pub fn get_time_control(&self) -> &TimeControl {
+        self.timer.get_time_control()
+    }
+
+    /// The clocks after each move of the mainline, unknown for moves read from PGN without
+    /// `%clk` comments.
+    pub fn get_move_times(&self) -> Vec<Option<MoveTime>> {
+        self.history
+            .get_mainline()
+            .into_iter()
+            .map(|turn| turn.time)
+            .collect()
+    }
+
     pub fn get_pieces(&self) -> Vec<(Piece, Position)> {
         let mut pieces: Vec<(Piece, Position)> = vec![];
         for x in 0..8 {
@@ -376,68 +665,226 @@ impl Game {
         pieces
     }
 
-    pub fn get_rounds_info(&self) -> Vec<RoundInfo> {
-        self.history
-            .turns
-            .chunks(2)
-            .map(|turns| RoundInfo {
-                white_string: format!("{}", turns[0]),
-                black_string: turns
-                    .get(1)
-                    .map_or("...".to_string(), |black_turn| format!("{black_turn}")),
-            })
-            .collect()
+    /// The moves of the mainline by round, each with the variations branching off it.
+    pub fn get_rounds_info(&self, notation: Notation) -> Vec<RoundInfo> {
+        let mut rounds: Vec<RoundInfo> = vec![];
+        let mut nodes = self.history.get_first_moves();
+        let mut state = self.history.get_initial_state();
+        let mut path = vec![];
+        while let Some((node, alternatives)) = nodes.split_first() {
+            let ply = path.len();
+            path.push(0);
+            if state.player == Color::White || rounds.is_empty() {
+                rounds.push(RoundInfo {
+                    number: self.history.get_fullmove_number_at(ply),
+                    white_string: "...".to_string(),
+                    black_string: "...".to_string(),
+                    white_time: None,
+                    black_time: None,
+                    white_path: None,
+                    black_path: None,
+                    variations: vec![],
+                });
+            }
+            let round = rounds.last_mut().unwrap();
+            let san = node.turn.mv.to_san(state, notation) + &node.annotation.get_move_glyphs();
+            match state.player {
+                Color::White => {
+                    round.white_string = san;
+                    round.white_time = node.turn.time;
+                    round.white_path = Some(path.clone());
+                }
+                Color::Black => {
+                    round.black_string = san;
+                    round.black_time = node.turn.time;
+                    round.black_path = Some(path.clone());
+                }
+            }
+            for (i, alternative) in alternatives.iter().enumerate() {
+                let mut variation_path = path.clone();
+                variation_path[ply] = i + 1;
+                self.add_variation_info(
+                    &mut round.variations,
+                    alternative,
+                    state,
+                    variation_path,
+                    1,
+                    notation,
+                );
+            }
+            state = &node.turn.board_state;
+            nodes = &node.children;
+        }
+        rounds
     }
 
-    pub fn get_current_round(&self) -> usize {
-        self.history.get_current_round()
+    /// Adds the variation starting with `first`, played from `state`, to `variations`, followed
+    /// by the variations branching off it one level deeper.
+    fn add_variation_info<'a>(
+        &self,
+        variations: &mut Vec<VariationInfo>,
+        first: &'a Node,
+        mut state: &'a BoardState,
+        mut path: Vec<usize>,
+        depth: usize,
+        notation: Notation,
+    ) {
+        let index = variations.len();
+        variations.push(VariationInfo {
+            depth,
+            moves: vec![],
+        });
+        let mut node = first;
+        loop {
+            let number = self.history.get_fullmove_number_at(path.len() - 1);
+            let san = node.turn.mv.to_san(state, notation) + &node.annotation.get_move_glyphs();
+            let moves = &mut variations[index].moves;
+            let text = match state.player {
+                Color::White => format!("{number}. {san}"),
+                Color::Black if moves.is_empty() => format!("{number}... {san}"),
+                Color::Black => san,
+            };
+            moves.push(VariationMove {
+                text,
+                path: path.clone(),
+            });
+            state = &node.turn.board_state;
+            let Some((next, alternatives)) = node.children.split_first() else {
+                break;
+            };
+            path.push(0);
+            for (i, alternative) in alternatives.iter().enumerate() {
+                let mut variation_path = path.clone();
+                *variation_path.last_mut().unwrap() = i + 1;
+                self.add_variation_info(
+                    variations,
+                    alternative,
+                    state,
+                    variation_path,
+                    depth + 1,
+                    notation,
+                );
+            }
+            node = next;
+        }
     }
 
-    pub(super) fn get_current_turn_index(&self) -> usize {
-        self.history.get_current_turn_index()
+    pub fn get_current_round(&self) -> usize {
+        self.history.get_current_round()
     }
 
     pub fn get_real_player(&self) -> Color {
         self.history.get_real_state().player
     }
 
+    pub(super) fn stop_timer(&mut self) {
+        self.timer.stop();
+    }
+
+    /// Ends the game if the player to move has run out of time.
     pub fn trigger_timeout(&mut self) {
+        if self.status.is_game_over() || !self.get_active_time().is_zero() {
+            return;
+        }
         self.timer.stop();
-        self.update_status();
+        self.status.update(self.get_timeout_status());
+        self.history.update_status(self.status);
+        self.draw_offer = None;
     }
 
     pub fn get_fen_str(&self) -> String {
-        let mut fen = String::with_capacity(MAX_FEN_STR);
-        let mut empty_count = 0;
-        for y in (0..8).rev() {
-            for x in 0..8 {
-                if let Some(piece) = self.get_piece(&Position { x, y }) {
-                    if empty_count > 0 {
-                        fen.push_str(&empty_count.to_string());
-                        empty_count = 0;
-                    }
-                    fen.push(piece.get_fen_char());
-                } else {
-                    empty_count += 1;
-                }
-            }
-            if empty_count > 0 {
-                fen.push_str(&empty_count.to_string());
-                empty_count = 0;
+        format!(
+            "{} {} {}",
+            self.get_current_state().get_fen_str(),
+            self.history.get_halfmove_clock(),
+            self.history.get_fullmove_number()
+        )
+    }
+
+    /// The Zobrist hash of the current position, equal for positions that are the same under the
+    /// repetition rules.
+    pub fn position_hash(&self) -> u64 {
+        self.get_current_state().get_zobrist_key()
+    }
+
+    /// The tags of a game read from PGN, in their order there.
+    pub fn get_tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub(super) fn set_tags(&mut self, tags: Vec<(String, String)>) {
+        self.tags = tags;
+    }
+
+    /// Reads every game in a PGN file.
+    pub fn from_pgn(pgn: &str) -> Result<Vec<Self>, ChessError> {
+        pgn::parse_games(pgn)
+    }
+
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_tags(&[])
+    }
+
+    /// Exports the game as PGN. The given tags replace the generated ones of the same name.
+    pub fn to_pgn_with_tags(&self, tags: &[(&str, &str)]) -> String {
+        let mut pgn = Pgn::new(self.status.get_pgn_result());
+        pgn.set_tag("TimeControl", &self.get_time_control().to_string());
+        // the imported tags, except those the position and the result decide
+        for (name, value) in &self.tags {
+            if !matches!(name.as_str(), "Result" | "Variant" | "SetUp" | "FEN") {
+                pgn.set_tag(name, value);
             }
-            fen.push('/');
-        }
-        fen.push_str(&format!(
-            " {} {} {} {} {}",
-            self.get_current_player().get_fen_char(),
-            self.get_current_state().castling_rights.get_fen_str(),
-            self.get_current_state()
-                .en_passant_position
-                .map_or("-".to_string(), |pos| pos.to_string()),
-            self.history.fifty_move_count,
-            self.get_current_turn_index() / 2 + 1
+        }
+        let variant = self.get_variant();
+        if variant != Variant::Standard {
+            pgn.set_tag("Variant", &variant.to_string());
+        } else if self.history.get_initial_state().is_chess960() {
+            pgn.set_tag("Variant", "Chess960");
+        }
+        if !self.history.has_standard_start() {
+            pgn.set_tag("SetUp", "1");
+            pgn.set_tag("FEN", &self.history.get_initial_fen());
+        }
+        for (name, value) in tags {
+            pgn.set_tag(name, value);
+        }
+        pgn.set_comment(pgn::format_comment(
+            None,
+            self.history.get_initial_annotation(),
         ));
-        fen
+        pgn.set_moves(
+            Self::get_pgn_moves(
+                self.history.get_first_moves(),
+                self.history.get_initial_state(),
+            ),
+            self.history.get_initial_fullmove_number(),
+            self.history.get_initial_state().player == Color::Black,
+        );
+        pgn.to_string()
+    }
+
+    /// The line starting with the first of `nodes`, played from `state`, with the clocks and
+    /// annotations of its moves and the variations replacing them.
+    fn get_pgn_moves<'a>(nodes: &'a [Node], mut state: &'a BoardState) -> Vec<PgnMove> {
+        let mut moves = vec![];
+        let mut nodes = nodes;
+        while let Some((node, alternatives)) = nodes.split_first() {
+            moves.push(PgnMove {
+                san: node.turn.mv.to_san(state, Notation::Ascii),
+                nags: node.annotation.nags.clone(),
+                comment: pgn::format_comment(
+                    node.turn.time.map(|time| time.get_time(state.player)),
+                    &node.annotation,
+                ),
+                variations: alternatives
+                    .iter()
+                    .map(|alternative| Self::get_pgn_moves(slice::from_ref(alternative), state))
+                    .collect(),
+            });
+            state = &node.turn.board_state;
+            nodes = &node.children;
+        }
+        moves
     }
 
     pub fn get_current_move(&self) -> Option<Move> {
@@ -453,29 +900,23 @@ impl Game {
 
         // from-to square of current move
         if let Some(mv) = &self.get_current_move() {
-            info.push((mv.from, MOVED_CLASS.into()));
+            if mv.drop.is_none() {
+                info.push((mv.from, MOVED_CLASS.into()));
+            }
             info.push((mv.to, MOVED_CLASS.into()));
         }
 
         // highlight king depending on status
         if let Some(turn) = self.history.get_current_turn() {
             match turn.status {
-                GameStatus::Check(color) => match color {
-                    Color::White => {
-                        info.push((turn.board_state.white_king_position, CHECK_CLASS.into()))
-                    }
-                    Color::Black => {
-                        info.push((turn.board_state.black_king_position, CHECK_CLASS.into()))
-                    }
-                },
-                GameStatus::Checkmate(color) => {
-                    match color {
-                        Color::White => info
-                            .push((turn.board_state.white_king_position, CHECKMATE_CLASS.into())),
-                        Color::Black => info
-                            .push((turn.board_state.black_king_position, CHECKMATE_CLASS.into())),
-                    }
-                }
+                GameStatus::Check(color) => info.push((
+                    turn.board_state.get_king_position(color),
+                    CHECK_CLASS.into(),
+                )),
+                GameStatus::Checkmate(color) => info.push((
+                    turn.board_state.get_king_position(color),
+                    CHECKMATE_CLASS.into(),
+                )),
                 _ => (),
             }
         }
@@ -485,44 +926,495 @@ impl Game {
     pub fn game_over(&self) -> bool {
         self.status.is_game_over()
     }
+
+    /// Who won and why, once the game is over.
+    pub fn result(&self) -> Option<GameResult> {
+        self.status.get_result()
+    }
 }
-struct GameBuilder {
-    start_time: Duration,
+
+/// Sets up a game: its variant, start position, time control and clock.
+pub struct GameBuilder {
+    clock: Arc<dyn Clock>,
+    time_control: TimeControl,
     state: BoardState,
+    halfmove_clock: u32,
+    fullmove_number: usize,
+    variant: Variant,
+    automatic_draws: bool,
 }
 
 impl Default for GameBuilder {
     fn default() -> Self {
         Self {
-            start_time: DEFAULT_DURATION,
+            clock: Arc::new(SystemClock::default()),
+            time_control: TimeControl::default(),
             state: BoardState::default(),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            variant: Variant::default(),
+            automatic_draws: false,
         }
     }
 }
 
 impl GameBuilder {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self::default()
     }
 
-    fn build(self) -> Game {
+    /// Builds the game, failing if the side not to move is in check.
+    pub fn build(self) -> Result<Game, ChessError> {
+        let mut state = self.state;
+        state.variant = self.variant;
+        if self.variant != Variant::ThreeCheck {
+            state.checks_given = [0; 2];
+        }
+        if self.variant != Variant::Crazyhouse {
+            state.pockets = Pockets::default();
+            state.promoted = Bitboard::EMPTY;
+        }
+        let mut opponent_to_move = state;
+        opponent_to_move.player = !opponent_to_move.player;
+        if opponent_to_move.is_in_check() {
+            return Err(ChessError::FenOpponentInCheck);
+        }
+
         let mut game = Game {
             valid_moves: HashSet::default(),
-            history: History::with_state(self.state),
-            timer: Timer::with_duration(self.start_time),
+            history: History::with_state(state, self.halfmove_clock, self.fullmove_number),
+            timer: Timer::new(self.clock, self.time_control, state.player),
             status: GameStatus::default(),
+            draw_offer: None,
+            takeback_request: None,
+            automatic_draws: self.automatic_draws,
+            tags: vec![],
         };
         game.add_moves();
-        game
+        game.update_status();
+        Ok(game)
     }
 
-    fn start_time(mut self, start_time: Duration) -> Self {
-        self.start_time = start_time;
+    /// Gives both players `start_time` for the whole game.
+    pub fn start_time(mut self, start_time: Duration) -> Self {
+        self.time_control = TimeControl::new(start_time);
         self
     }
 
-    fn state(mut self, state: BoardState) -> Self {
-        self.state = state;
+    /// Reads the time for the players' clocks from `clock` instead of the system.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
         self
     }
+
+    pub fn time_control(mut self, time_control: TimeControl) -> Self {
+        self.time_control = time_control;
+        self
+    }
+
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// See `Game::set_automatic_draws`.
+    pub fn automatic_draws(mut self, automatic_draws: bool) -> Self {
+        self.automatic_draws = automatic_draws;
+        self
+    }
+
+    /// Starts from the position described by a FEN string. Three-check positions may have the
+    /// remaining checks as an extra field before the halfmove clock, e.g. `3+3`, and Crazyhouse
+    /// positions the pockets after the piece placement, e.g. `RNBQKBNR[Qp]`.
+    pub fn fen(mut self, fen: &str) -> Result<Self, ChessError> {
+        let fen: Fen = fen.parse()?;
+        self.state = fen.state;
+        self.halfmove_clock = fen.halfmove_clock;
+        self.fullmove_number = fen.fullmove_number;
+        Ok(self)
+    }
+
+    /// Starts from the Chess960 start position with the given index from 0 to 959.
+    pub fn chess960(mut self, index: u16) -> Result<Self, ChessError> {
+        self.state = BoardState::chess960(index)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game_result::{Outcome, Termination},
+        test_utils::play,
+    };
+
+    #[test]
+    fn test_get_rounds_info() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        play(&mut game, &["e8d7", "e2e4", "d7e6"]);
+        let rounds: Vec<_> = game
+            .get_rounds_info(Notation::Ascii)
+            .into_iter()
+            .map(|info| (info.number, info.white_string, info.black_string))
+            .collect();
+        assert_eq!(
+            rounds,
+            [
+                (40, "...".to_string(), "Kd7".to_string()),
+                (41, "e4".to_string(), "Ke6".to_string())
+            ]
+        );
+        assert_eq!(game.get_current_round(), 2);
+        game.go_back_a_move();
+        assert_eq!(game.get_current_round(), 2);
+        game.go_back_a_move();
+        assert_eq!(game.get_current_round(), 1);
+        game.go_to_start();
+        assert_eq!(game.get_current_round(), 0);
+    }
+
+    #[test]
+    fn test_variations() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4", "e7e5", "g1f3", "b8c6"]);
+        game.go_to_move(&[0]).unwrap();
+        play(&mut game, &["c7c5", "g1f3", "d7d6"]);
+        assert!(game.is_in_variation());
+        // the game goes on from the mainline
+        assert_eq!(game.get_real_player(), Color::White);
+
+        game.go_to_move(&[0, 1]).unwrap();
+        play(&mut game, &["b1c3"]);
+        let rounds = game.get_rounds_info(Notation::Ascii);
+        let variations: Vec<_> = rounds[0]
+            .variations
+            .iter()
+            .map(|variation| {
+                let moves: Vec<_> = variation.moves.iter().map(|mv| mv.text.as_str()).collect();
+                (variation.depth, moves.join(" "))
+            })
+            .collect();
+        assert_eq!(
+            variations,
+            [
+                (1, "1... c5 2. Nf3 d6".to_string()),
+                (2, "2. Nc3".to_string())
+            ]
+        );
+        assert_eq!(rounds[1].black_path, Some(vec![0, 0, 0, 0]));
+
+        // the game continues from the promoted line
+        game.go_to_move(&[0, 1, 0, 0]).unwrap();
+        game.promote_variation().unwrap();
+        assert!(!game.is_replaying());
+        assert_eq!(game.get_real_player(), Color::White);
+        play(&mut game, &["d2d4"]);
+        assert_eq!(game.get_current_path(), [0, 0, 0, 0, 0]);
+
+        game.go_to_move(&[0, 0, 1]).unwrap();
+        game.delete_variation().unwrap();
+        assert_eq!(game.get_current_path(), [0, 0]);
+        assert!(game.delete_variation().is_err());
+        assert!(game.go_to_move(&[0, 0, 1]).is_err());
+        assert!(game.go_to_move(&[0, 1]).is_ok());
+        assert!(game.get_rounds_info(Notation::Ascii)[1]
+            .variations
+            .is_empty());
+    }
+
+    #[test]
+    fn test_variations_after_game_over() {
+        let mut game = Game::new();
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert_eq!(game.status, GameStatus::Checkmate(Color::White));
+        game.go_back_a_move();
+        game.move_piece(Move::from_lan("d8g5").unwrap()).unwrap();
+        assert_eq!(game.status, GameStatus::Checkmate(Color::White));
+        assert!(game.move_piece(Move::from_lan("d2d4").unwrap()).is_ok());
+        game.go_back_a_move();
+        game.previous_variation();
+        assert_eq!(
+            game.get_current_move(),
+            Some(Move::from_lan("d8h4").unwrap())
+        );
+        assert_eq!(
+            game.history.get_current_turn().unwrap().status,
+            GameStatus::Checkmate(Color::White)
+        );
+    }
+
+    fn can_castle(fen: &str, lan: &str) -> bool {
+        let game = Game::from_fen(fen).unwrap();
+        game.is_move_valid(&Move::from_lan(lan).unwrap()).is_ok()
+    }
+
+    #[test]
+    fn test_castling() {
+        assert!(can_castle("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1"));
+        assert!(can_castle("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1"));
+        assert!(can_castle("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"));
+        // out of check
+        assert!(!can_castle("r3k2r/8/8/8/8/8/8/R3K1qR w KQkq - 0 1", "e1c1"));
+        assert!(!can_castle(
+            "r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1",
+            "e1g1"
+        ));
+        assert!(!can_castle(
+            "r3k2r/8/8/1B6/8/8/8/R3K2R b KQkq - 0 1",
+            "e8g8"
+        ));
+        // through an attacked square
+        assert!(!can_castle(
+            "r3k2r/8/8/8/8/8/5r2/R3K2R w KQkq - 0 1",
+            "e1g1"
+        ));
+        assert!(!can_castle(
+            "r3k2r/8/8/8/8/2n5/8/R3K2R w KQkq - 0 1",
+            "e1c1"
+        ));
+        assert!(!can_castle(
+            "r3k2r/8/8/8/8/8/6p1/R3K2R w KQkq - 0 1",
+            "e1g1"
+        ));
+        assert!(!can_castle(
+            "r3k2r/2P5/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "e8c8"
+        ));
+        // into an attacked square
+        assert!(!can_castle(
+            "r3k2r/8/8/8/8/8/6r1/R3K2R w KQkq - 0 1",
+            "e1g1"
+        ));
+        // only the rook passes an attacked square
+        assert!(can_castle("1r2k2r/8/8/8/8/8/8/R3K2R w KQk - 0 1", "e1c1"));
+        // pieces on b1/b8
+        assert!(!can_castle("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1", "e1c1"));
+        assert!(!can_castle("rn2k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8"));
+        assert!(can_castle("rn2k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8g8"));
+    }
+
+    #[test]
+    fn test_underpromotion() {
+        let mut game = Game::from_fen("1r6/P2k4/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+        let (from, to) = (Position::new(0, 6), Position::new(1, 7));
+        assert!(game.is_promotion(&from, &to));
+        assert!(game.is_move_valid(&Move::new(from, to)).is_err());
+        for piece in Piece::get_promotion_pieces(Color::White) {
+            assert!(game
+                .is_move_valid(&Move::with_promotion(from, to, piece))
+                .is_ok());
+        }
+        game.move_piece(Move::with_promotion(from, to, Piece::Knight(Color::White)))
+            .unwrap();
+        assert_eq!(game.get_piece(&to), Some(Piece::Knight(Color::White)));
+        assert!(game.is_in_check());
+    }
+
+    #[test]
+    fn test_draw_offers() {
+        let mut game = Game::new();
+        assert!(matches!(
+            game.respond_to_draw(Color::Black, true),
+            Err(ChessError::NoDrawOffer)
+        ));
+
+        // an offer lapses when its player moves, before or after the opponent's reply
+        game.offer_draw(Color::White).unwrap();
+        play(&mut game, &["e2e4"]);
+        assert_eq!(game.get_draw_offer(), None);
+        game.offer_draw(Color::White).unwrap();
+        play(&mut game, &["e7e5"]);
+        assert_eq!(game.get_draw_offer(), Some(Color::White));
+        play(&mut game, &["g1f3"]);
+        assert_eq!(game.get_draw_offer(), None);
+
+        // only the opponent can respond
+        game.offer_draw(Color::White).unwrap();
+        assert!(matches!(
+            game.respond_to_draw(Color::White, true),
+            Err(ChessError::OwnDrawOffer)
+        ));
+        assert_eq!(game.get_draw_offer(), Some(Color::White));
+        game.respond_to_draw(Color::Black, false).unwrap();
+        assert!(!game.game_over());
+
+        game.offer_draw(Color::Black).unwrap();
+        game.respond_to_draw(Color::White, true).unwrap();
+        assert_eq!(game.status, GameStatus::Draw(DrawKind::Agreement));
+        assert!(game.move_piece(Move::from_lan("b8c6").unwrap()).is_err());
+        assert!(game.resign(Color::Black).is_err());
+    }
+
+    #[test]
+    fn test_take_back() {
+        let mut game = Game::new();
+        assert!(game.take_back(1).is_err());
+        play(&mut game, &["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6"]);
+        // taking back black's blunder lets black choose another move
+        game.take_back(1).unwrap();
+        assert_eq!(game.get_real_player(), Color::Black);
+        play(&mut game, &["d8e7"]);
+        assert_eq!(game.get_rounds_info(Notation::Ascii)[2].black_string, "Qe7");
+        assert_eq!(game.get_current_path(), [0; 6]);
+
+        // the check given by the move taken back is gone
+        play(&mut game, &["h5f7"]);
+        assert!(game.is_in_check());
+        game.take_back(1).unwrap();
+        assert_eq!(game.status, GameStatus::Ongoing);
+
+        game.take_back(6).unwrap();
+        assert_eq!(game.get_fen_str(), Game::new().get_fen_str());
+        assert!(!game.is_timer_active());
+
+        // a finished game goes on from before the mate, with the clocks running again
+        play(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+        assert!(game.game_over());
+        game.take_back(1).unwrap();
+        assert_eq!(game.status, GameStatus::Ongoing);
+        assert!(game.is_timer_active());
+        play(&mut game, &["d7d5"]);
+
+        game.resign(Color::White).unwrap();
+        game.take_back(1).unwrap();
+        assert!(!game.game_over());
+        assert_eq!(game.result(), None);
+    }
+
+    #[test]
+    fn test_takeback_requests() {
+        let mut game = Game::new();
+        assert!(game.request_takeback(Color::White).is_err());
+        play(&mut game, &["e2e4"]);
+        assert!(game.request_takeback(Color::Black).is_err());
+        assert!(game.respond_to_takeback(Color::Black, true).is_err());
+
+        // only the opponent answers a request
+        game.request_takeback(Color::White).unwrap();
+        assert!(matches!(
+            game.respond_to_takeback(Color::White, true),
+            Err(ChessError::OwnTakebackRequest)
+        ));
+        game.respond_to_takeback(Color::Black, false).unwrap();
+        assert_eq!(game.get_takeback_request(), None);
+
+        // a move declines the request
+        game.request_takeback(Color::White).unwrap();
+        play(&mut game, &["e7e5"]);
+        assert_eq!(game.get_takeback_request(), None);
+
+        // white's last move and black's reply are taken back
+        game.request_takeback(Color::White).unwrap();
+        game.respond_to_takeback(Color::Black, true).unwrap();
+        assert_eq!(game.get_current_path(), []);
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut game = Game::new();
+        assert_eq!(game.result(), None);
+        game.resign(Color::White).unwrap();
+        assert!(game.game_over());
+        let result = game.result().unwrap();
+        assert_eq!(
+            result,
+            GameResult {
+                outcome: Outcome::BlackWins,
+                reason: Termination::Resignation
+            }
+        );
+        assert_eq!(result.to_string(), "Black wins by resignation");
+        assert!(game.to_pgn().contains("0-1"));
+        assert!(game.offer_draw(Color::Black).is_err());
+    }
+
+    #[test]
+    fn test_repetition_draws() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let mut game = Game::new();
+        for lan in shuffle.iter().cycle().take(8) {
+            game.move_piece(Move::from_lan(lan).unwrap()).unwrap();
+        }
+        assert!(!game.game_over());
+        assert!(game.can_claim_draw());
+
+        let mut casual_game = game.clone();
+        for lan in shuffle.iter().cycle().take(8) {
+            game.move_piece(Move::from_lan(lan).unwrap()).unwrap();
+        }
+        assert_eq!(game.status, GameStatus::Draw(DrawKind::FivefoldRepetition));
+
+        casual_game.claim_draw().unwrap();
+        assert_eq!(casual_game.status, GameStatus::Draw(DrawKind::Repetition));
+        assert!(casual_game.claim_draw().is_err());
+
+        let mut casual_game = Game::new();
+        casual_game.set_automatic_draws(true);
+        for lan in shuffle.iter().cycle().take(8) {
+            casual_game
+                .move_piece(Move::from_lan(lan).unwrap())
+                .unwrap();
+        }
+        assert_eq!(casual_game.status, GameStatus::Draw(DrawKind::Repetition));
+    }
+
+    #[test]
+    fn test_move_count_draws() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/R7/4K3 w - - 99 80").unwrap();
+        assert!(!game.can_claim_draw());
+        game.move_piece(Move::from_lan("a2b2").unwrap()).unwrap();
+        assert!(game.can_claim_draw());
+        assert!(!game.game_over());
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/R7/4K3 w - - 149 80").unwrap();
+        game.move_piece(Move::from_lan("a2b2").unwrap()).unwrap();
+        assert_eq!(game.status, GameStatus::Draw(DrawKind::SeventyFiveMoveRule));
+
+        // checkmate on the last move still counts
+        let mut game = Game::from_fen("6k1/8/6K1/8/8/8/8/R7 w - - 149 80").unwrap();
+        game.move_piece(Move::from_lan("a1a8").unwrap()).unwrap();
+        assert_eq!(game.status, GameStatus::Checkmate(Color::Black));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let flag_fall = |fen: &str| {
+            let mut game = Game::builder()
+                .fen(fen)
+                .unwrap()
+                .start_time(Duration::ZERO)
+                .build()
+                .unwrap();
+            game.trigger_timeout();
+            assert!(game.game_over());
+            game.status
+        };
+        assert_eq!(
+            flag_fall("4k3/8/8/8/8/8/8/R3K3 b - - 0 1"),
+            GameStatus::Timeout(Color::Black)
+        );
+        assert_eq!(
+            flag_fall("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"),
+            GameStatus::Draw(DrawKind::TimeoutVsInsufficientMaterial)
+        );
+        // a knight can only mate with the help of the opponent's own pieces
+        assert_eq!(
+            flag_fall("n3k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            GameStatus::Draw(DrawKind::TimeoutVsInsufficientMaterial)
+        );
+        assert_eq!(
+            flag_fall("n3k3/8/8/8/8/8/4P3/4K3 w - - 0 1"),
+            GameStatus::Timeout(Color::White)
+        );
+        // bishops on one color cannot mate a king only blocked on that color
+        assert_eq!(
+            flag_fall("b1b1k3/8/8/8/8/8/8/3BK3 w - - 0 1"),
+            GameStatus::Draw(DrawKind::TimeoutVsInsufficientMaterial)
+        );
+        assert_eq!(
+            flag_fall("b1b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"),
+            GameStatus::Timeout(Color::White)
+        );
+    }
 }
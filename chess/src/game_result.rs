@@ -0,0 +1,99 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::color::Color;
+
+/// How a finished game ended and who, if anyone, won.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameResult {
+    pub outcome: Outcome,
+    pub reason: Termination,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Termination {
+    Checkmate,
+    Resignation,
+    Timeout,
+    Abandonment,
+    Stalemate,
+    InsufficientMaterial,
+    TimeoutVsInsufficientMaterial,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    FiftyMoveRule,
+    SeventyFiveMoveRule,
+    Agreement,
+    KingInTheCenter,
+    ThreeChecks,
+    Explosion,
+    /// The result of an imported game, which does not say how it ended.
+    Unknown,
+}
+
+impl Outcome {
+    /// The outcome of `loser` losing the game.
+    pub(super) fn from_loser(loser: Color) -> Self {
+        match loser {
+            Color::White => Self::BlackWins,
+            Color::Black => Self::WhiteWins,
+        }
+    }
+
+    /// The PGN result token, e.g. `1-0` when white wins.
+    pub(super) fn get_pgn_result(self) -> &'static str {
+        match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+        }
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::Checkmate => "checkmate",
+            Self::Resignation => "resignation",
+            Self::Timeout => "timeout",
+            Self::Abandonment => "abandonment",
+            Self::Stalemate => "stalemate",
+            Self::InsufficientMaterial => "insufficient material",
+            Self::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
+            Self::ThreefoldRepetition => "threefold repetition",
+            Self::FivefoldRepetition => "fivefold repetition",
+            Self::FiftyMoveRule => "the fifty-move rule",
+            Self::SeventyFiveMoveRule => "the seventy-five-move rule",
+            Self::Agreement => "agreement",
+            Self::KingInTheCenter => "king in the center",
+            Self::ThreeChecks => "three checks",
+            Self::Explosion => "explosion",
+            Self::Unknown => "an unknown reason",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+impl fmt::Display for GameResult {
+    /// E.g. `White wins by checkmate` or `Draw by stalemate`, or just `White wins` when the
+    /// reason is unknown.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let outcome = match self.outcome {
+            Outcome::WhiteWins => "White wins",
+            Outcome::BlackWins => "Black wins",
+            Outcome::Draw => "Draw",
+        };
+        match self.reason {
+            Termination::Unknown => write!(f, "{outcome}"),
+            reason => write!(f, "{outcome} by {reason}"),
+        }
+    }
+}
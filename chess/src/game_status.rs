@@ -1,4 +1,7 @@
-use crate::color::Color;
+use crate::{
+    color::Color,
+    game_result::{GameResult, Outcome, Termination},
+};
 
 #[derive(Clone, Copy, Default, PartialEq, Debug)]
 pub(super) enum GameStatus {
@@ -8,7 +11,19 @@ pub(super) enum GameStatus {
     Check(Color),
     Checkmate(Color),
     Timeout(Color),
+    /// The given player resigned.
+    Resigned(Color),
+    /// The given player left the game.
+    Abandoned(Color),
+    /// The given player's opponent got their king to the center in King of the Hill.
+    KingOfTheHill(Color),
+    /// The given player was checked for the third time in Three-check.
+    ThreeChecks(Color),
+    /// The given player's king was blown up in Atomic.
+    Exploded(Color),
     Draw(DrawKind),
+    /// The result of an imported game that its moves do not decide, e.g. after a resignation.
+    Recorded(Outcome),
 }
 
 impl GameStatus {
@@ -20,14 +35,76 @@ impl GameStatus {
     }
 
     pub(super) fn is_game_over(&self) -> bool {
-        matches!(self, GameStatus::Draw(..) | GameStatus::Checkmate(..))
+        matches!(
+            self,
+            GameStatus::Draw(..)
+                | GameStatus::Checkmate(..)
+                | GameStatus::Resigned(..)
+                | GameStatus::Abandoned(..)
+                | GameStatus::Timeout(..)
+                | GameStatus::KingOfTheHill(..)
+                | GameStatus::ThreeChecks(..)
+                | GameStatus::Exploded(..)
+                | GameStatus::Recorded(..)
+        )
+    }
+
+    /// The result of a finished game.
+    pub(super) fn get_result(&self) -> Option<GameResult> {
+        let (outcome, reason) = match *self {
+            GameStatus::Checkmate(player) => (Outcome::from_loser(player), Termination::Checkmate),
+            GameStatus::Timeout(player) => (Outcome::from_loser(player), Termination::Timeout),
+            GameStatus::Resigned(player) => (Outcome::from_loser(player), Termination::Resignation),
+            GameStatus::Abandoned(player) => {
+                (Outcome::from_loser(player), Termination::Abandonment)
+            }
+            GameStatus::KingOfTheHill(player) => {
+                (Outcome::from_loser(player), Termination::KingInTheCenter)
+            }
+            GameStatus::ThreeChecks(player) => {
+                (Outcome::from_loser(player), Termination::ThreeChecks)
+            }
+            GameStatus::Exploded(player) => (Outcome::from_loser(player), Termination::Explosion),
+            GameStatus::Draw(kind) => (Outcome::Draw, kind.get_termination()),
+            GameStatus::Recorded(outcome) => (outcome, Termination::Unknown),
+            GameStatus::NotStarted | GameStatus::Ongoing | GameStatus::Check(..) => return None,
+        };
+        Some(GameResult { outcome, reason })
+    }
+
+    /// The PGN result token, e.g. `1-0` when black is checkmated.
+    pub(super) fn get_pgn_result(&self) -> &'static str {
+        self.get_result()
+            .map_or("*", |result| result.outcome.get_pgn_result())
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub(super) enum DrawKind {
+    /// Claimed, or automatic in casual games.
     FiftyMoveRule,
+    /// Threefold repetition, claimed or automatic in casual games.
     Repetition,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
     Stalemate,
     InsufficientMaterial,
+    /// A flag fell but the opponent could not have checkmated.
+    TimeoutVsInsufficientMaterial,
+    Agreement,
+}
+
+impl DrawKind {
+    fn get_termination(self) -> Termination {
+        match self {
+            DrawKind::FiftyMoveRule => Termination::FiftyMoveRule,
+            DrawKind::Repetition => Termination::ThreefoldRepetition,
+            DrawKind::FivefoldRepetition => Termination::FivefoldRepetition,
+            DrawKind::SeventyFiveMoveRule => Termination::SeventyFiveMoveRule,
+            DrawKind::Stalemate => Termination::Stalemate,
+            DrawKind::InsufficientMaterial => Termination::InsufficientMaterial,
+            DrawKind::TimeoutVsInsufficientMaterial => Termination::TimeoutVsInsufficientMaterial,
+            DrawKind::Agreement => Termination::Agreement,
+        }
+    }
 }
@@ -1,148 +1,561 @@
-use std::collections::HashMap;
+use crate::{
+    annotation::Annotation, board_state::BoardState, color::Color, game_status::GameStatus,
+    move_time::MoveTime, moves::Move, piece::Piece, turn::Turn,
+};
 
-use crate::{board_state::BoardState, game_status::GameStatus, moves::Move, turn::Turn};
+/// A move of the history, with the moves played after it. The first of them continues its line,
+/// the others are variations.
+#[derive(Clone)]
+pub(super) struct Node {
+    pub(super) turn: Turn,
+    pub(super) annotation: Annotation,
+    pub(super) children: Vec<Node>,
+}
 
+/// The moves of a game as a tree: the mainline of the game itself and the variations branching
+/// off it. A move is found by its path, the index of the move chosen among the alternatives at
+/// every ply.
 #[derive(Clone)]
 pub(super) struct History {
-    pub(super) turns: Vec<Turn>,
-    pub(super) repetition_counter: HashMap<BoardState, usize>,
-    current_turn_index: usize,
-    pub(super) fifty_move_count: u8,
+    /// The first moves: the mainline's, then its alternatives.
+    first_moves: Vec<Node>,
+    current_path: Vec<usize>,
+    /// The state after the current move. It is read for every square of the board, so it is kept
+    /// up to date with `current_path` instead of walking the tree.
+    current_state: BoardState,
+    /// The number of moves of the mainline, whose path is all zeros.
+    mainline_len: usize,
+    /// The state at the end of the mainline.
+    real_state: BoardState,
+    /// Whether the current move is not the end of the mainline.
+    replaying: bool,
     initial_state: BoardState,
+    /// The annotation of the position before the first move.
+    initial_annotation: Annotation,
+    initial_fifty_move_count: u32,
+    initial_fullmove_number: usize,
 }
 
 impl History {
-    pub(super) fn with_state(initial_state: BoardState) -> Self {
-        Self {
+    pub(super) fn with_state(
+        initial_state: BoardState,
+        fifty_move_count: u32,
+        initial_fullmove_number: usize,
+    ) -> Self {
+        let mut history = Self {
             initial_state,
-            repetition_counter: vec![(initial_state, 1)].into_iter().collect(),
+            initial_fifty_move_count: fifty_move_count,
+            initial_fullmove_number,
             ..Default::default()
+        };
+        history.update_cache();
+        history
+    }
+
+    /// Brings the cached states up to date after the current path or the tree changed.
+    fn update_cache(&mut self) {
+        let mut children = &self.first_moves;
+        let mut real_state = &self.initial_state;
+        let mut mainline_len = 0;
+        while let Some(node) = children.first() {
+            real_state = &node.turn.board_state;
+            children = &node.children;
+            mainline_len += 1;
         }
+        self.real_state = *real_state;
+        self.mainline_len = mainline_len;
+        self.current_state = *self.get_board_state(&self.current_path);
+        self.replaying = self.current_path.len() != self.mainline_len
+            || self.current_path.iter().any(|&i| i != 0);
     }
 
-    pub(super) fn update_status(&mut self, status: GameStatus) {
-        self.get_real_turn_mut().unwrap().status = status;
+    /// The moves that can be played after the move at `path`.
+    fn get_children(&self, path: &[usize]) -> Option<&Vec<Node>> {
+        let mut children = &self.first_moves;
+        for &i in path {
+            children = &children.get(i)?.children;
+        }
+        Some(children)
     }
 
-    pub(super) fn get_real_turn(&self) -> Option<&Turn> {
-        self.turns.last()
+    fn get_children_mut(&mut self, path: &[usize]) -> Option<&mut Vec<Node>> {
+        let mut children = &mut self.first_moves;
+        for &i in path {
+            children = &mut children.get_mut(i)?.children;
+        }
+        Some(children)
     }
 
-    pub(super) fn get_real_turn_mut(&mut self) -> Option<&mut Turn> {
-        self.turns.last_mut()
+    fn get_node_mut(&mut self, path: &[usize]) -> Option<&mut Node> {
+        let (&last, parent) = path.split_last()?;
+        self.get_children_mut(parent)?.get_mut(last)
     }
 
-    pub(super) fn get_current_move(&self) -> Option<Move> {
-        if self.get_current_turn_index() == 0 {
-            None
-        } else {
-            Some(self.turns[self.get_current_turn_index() - 1].mv)
+    fn get_turn_mut(&mut self, path: &[usize]) -> Option<&mut Turn> {
+        Some(&mut self.get_node_mut(path)?.turn)
+    }
+
+    /// The turns from the first move to the move at `path`.
+    fn get_line(&self, path: &[usize]) -> Vec<&Turn> {
+        let mut turns = Vec::with_capacity(path.len());
+        let mut children = &self.first_moves;
+        for &i in path {
+            turns.push(&children[i].turn);
+            children = &children[i].children;
         }
+        turns
     }
 
-    pub(super) fn get_current_turn(&self) -> Option<Turn> {
-        if self.get_current_turn_index() == 0 {
-            None
-        } else {
-            Some(self.turns[self.get_current_turn_index() - 1])
+    pub(super) fn get_first_moves(&self) -> &[Node] {
+        &self.first_moves
+    }
+
+    pub(super) fn get_mainline_path(&self) -> Vec<usize> {
+        vec![0; self.mainline_len]
+    }
+
+    pub(super) fn get_mainline(&self) -> Vec<&Turn> {
+        self.get_line(&self.get_mainline_path())
+    }
+
+    pub(super) fn get_current_line(&self) -> Vec<&Turn> {
+        self.get_line(&self.current_path)
+    }
+
+    pub(super) fn get_current_path(&self) -> &[usize] {
+        &self.current_path
+    }
+
+    pub(super) fn update_status(&mut self, status: GameStatus) {
+        if let Some(turn) = self.get_real_turn_mut() {
+            turn.status = status;
         }
     }
 
-    pub(super) fn get_current_turn_index(&self) -> usize {
-        self.current_turn_index
+    pub(super) fn set_current_status(&mut self, status: GameStatus) {
+        let path = self.current_path.clone();
+        if let Some(turn) = self.get_turn_mut(&path) {
+            turn.status = status;
+        }
     }
 
-    pub(super) fn get_board_state(&self, turn: usize) -> &BoardState {
-        if turn == 0 {
-            &self.initial_state
-        } else {
-            &self.turns[turn - 1].board_state
+    pub(super) fn set_move_time(&mut self, time: Option<MoveTime>) {
+        let path = self.current_path.clone();
+        if let Some(turn) = self.get_turn_mut(&path) {
+            turn.time = time;
         }
     }
 
-    pub(super) fn get_real_state_repetition_count(&self) -> usize {
-        *self.repetition_counter.get(self.get_real_state()).unwrap()
+    /// The annotation of the current position.
+    pub(super) fn get_annotation(&self) -> &Annotation {
+        match self.current_path.split_last() {
+            Some((&last, parent)) => &self.get_children(parent).unwrap()[last].annotation,
+            None => &self.initial_annotation,
+        }
     }
 
-    fn update_fifty_move_info(&mut self, piece_captured: bool, pawn_moved: bool) {
-        if piece_captured || pawn_moved {
-            self.fifty_move_count = 0
-        } else {
-            self.fifty_move_count += 1;
+    pub(super) fn set_annotation(&mut self, annotation: Annotation) {
+        let path = self.current_path.clone();
+        match self.get_node_mut(&path) {
+            Some(node) => node.annotation = annotation,
+            None => self.initial_annotation = annotation,
         }
     }
 
-    fn update_repetition_info(&mut self, next_state: BoardState) {
-        *self.repetition_counter.entry(next_state).or_insert(0) += 1;
+    pub(super) fn get_initial_annotation(&self) -> &Annotation {
+        &self.initial_annotation
     }
 
-    fn add_turn(&mut self, turn: Turn) {
-        self.turns.drain(self.current_turn_index..self.turns.len());
-        self.turns.push(turn);
-        self.current_turn_index += 1
+    pub(super) fn get_real_turn(&self) -> Option<&Turn> {
+        self.get_mainline().pop()
     }
 
-    pub(super) fn add_info(&mut self, next_state: BoardState, mv: Move) {
-        let current_state = self.get_current_state();
-        let is_pawn = current_state.get_piece(&mv.from).unwrap().is_pawn();
-        let is_capture_move =
-            current_state.get_piece(&mv.to).is_some() || (is_pawn && mv.from.x != mv.to.x);
+    fn get_real_turn_mut(&mut self) -> Option<&mut Turn> {
+        let path = self.get_mainline_path();
+        self.get_turn_mut(&path)
+    }
 
-        self.update_fifty_move_info(is_capture_move, is_pawn);
-        self.update_repetition_info(next_state);
-        self.add_turn(Turn::new(next_state, mv, is_capture_move));
+    pub(super) fn get_current_move(&self) -> Option<Move> {
+        self.get_current_turn().map(|turn| turn.mv)
+    }
+
+    pub(super) fn get_current_turn(&self) -> Option<Turn> {
+        self.get_current_line().pop().copied()
     }
 
-    pub(super) fn get_fifty_move_count(&self) -> u8 {
-        self.fifty_move_count / 2
+    /// How many times the last position of the line to `path` has occurred on it.
+    pub(super) fn get_repetition_count(&self, path: &[usize]) -> usize {
+        let line = self.get_line(path);
+        let key = line
+            .last()
+            .map_or(&self.initial_state, |turn| &turn.board_state)
+            .get_zobrist_key();
+        let initial = (self.initial_state.get_zobrist_key() == key) as usize;
+        initial
+            + line
+                .iter()
+                .filter(|turn| turn.board_state.get_zobrist_key() == key)
+                .count()
+    }
+
+    /// The Zobrist keys of the positions before the current one on its line, the first one
+    /// first.
+    pub(super) fn get_previous_keys(&self) -> Vec<u64> {
+        let line = self.get_current_line();
+        let states = line.iter().map(|turn| &turn.board_state);
+        std::iter::once(&self.initial_state)
+            .chain(states)
+            .take(line.len())
+            .map(BoardState::get_zobrist_key)
+            .collect()
+    }
+
+    /// The moves by both players since the last capture or pawn move on the line to `path`.
+    pub(super) fn get_fifty_move_count(&self, path: &[usize]) -> u32 {
+        self.get_halfmove_clock_at(path) / 2
+    }
+
+    /// Plays `mv` from the current position, which gives `next_state`. A move played before
+    /// becomes the current one again, otherwise the move is added as the continuation of the
+    /// current line, or as a variation if the line already continues. Returns whether the move
+    /// was added.
+    pub(super) fn add_info(&mut self, next_state: BoardState, mv: Move) -> bool {
+        let current_state = self.get_current_state();
+        let is_capture_move = current_state.is_capture(&mv);
+        let path = self.current_path.clone();
+        let children = self
+            .get_children_mut(&path)
+            .expect("the current path leads to a move");
+        if let Some(i) = children.iter().position(|node| node.turn.mv == mv) {
+            self.current_path.push(i);
+            self.update_cache();
+            return false;
+        }
+        children.push(Node {
+            turn: Turn::new(next_state, mv, is_capture_move),
+            annotation: Annotation::default(),
+            children: vec![],
+        });
+        let i = children.len() - 1;
+        self.current_path.push(i);
+        self.update_cache();
+        true
+    }
+
+    fn get_board_state(&self, path: &[usize]) -> &BoardState {
+        self.get_line(path)
+            .pop()
+            .map_or(&self.initial_state, |turn| &turn.board_state)
     }
 
     pub(super) fn get_current_state(&self) -> &BoardState {
-        self.get_board_state(self.current_turn_index)
+        &self.current_state
     }
 
     pub(super) fn get_real_state(&self) -> &BoardState {
-        self.get_board_state(self.turns.len())
+        &self.real_state
     }
 
     pub(super) fn resume(&mut self) {
-        self.current_turn_index = self.turns.len()
+        self.current_path = self.get_mainline_path();
+        self.update_cache();
     }
 
     pub(super) fn previous_move(&mut self) {
-        if self.current_turn_index > 0 {
-            self.current_turn_index -= 1
-        }
+        self.current_path.pop();
+        self.update_cache();
     }
 
+    /// Follows the current line one move further.
     pub(super) fn next_move(&mut self) {
-        if self.current_turn_index < self.turns.len() {
-            self.current_turn_index += 1
+        if self
+            .get_children(&self.current_path)
+            .is_some_and(|children| !children.is_empty())
+        {
+            self.current_path.push(0);
+            self.update_cache();
         }
     }
 
     pub(super) fn go_to_start(&mut self) {
-        self.current_turn_index = 0
+        self.current_path.clear();
+        self.update_cache();
+    }
+
+    /// Makes the move at `path` the current one, if there is one.
+    pub(super) fn go_to(&mut self, path: &[usize]) -> bool {
+        let found = path.is_empty()
+            || path.split_last().is_some_and(|(&last, parent)| {
+                self.get_children(parent)
+                    .is_some_and(|children| last < children.len())
+            });
+        if found {
+            self.current_path = path.to_vec();
+            self.update_cache();
+        }
+        found
+    }
+
+    /// Moves to the next alternative to the current move when `forward`, else to the previous
+    /// one. Returns whether there was one.
+    pub(super) fn go_to_sibling(&mut self, forward: bool) -> bool {
+        let Some((&last, parent)) = self.current_path.split_last() else {
+            return false;
+        };
+        let count = self.get_children(parent).map_or(0, Vec::len);
+        let sibling = match forward {
+            true => last + 1,
+            false => last.wrapping_sub(1),
+        };
+        if sibling < count {
+            *self.current_path.last_mut().unwrap() = sibling;
+            self.update_cache();
+        }
+        sibling < count
+    }
+
+    /// The ply at which the variation holding the current move branches off its parent line.
+    fn get_variation_start(&self) -> Option<usize> {
+        self.current_path.iter().rposition(|&i| i != 0)
+    }
+
+    pub(super) fn is_in_variation(&self) -> bool {
+        self.get_variation_start().is_some()
+    }
+
+    /// Makes the variation holding the current move the main continuation of its parent line.
+    /// Returns whether the current move was in a variation.
+    pub(super) fn promote_variation(&mut self) -> bool {
+        let Some(ply) = self.get_variation_start() else {
+            return false;
+        };
+        let (parent, rest) = self.current_path.split_at(ply);
+        let i = rest[0];
+        let parent = parent.to_vec();
+        let siblings = self.get_children_mut(&parent).unwrap();
+        let variation = siblings.remove(i);
+        siblings.insert(0, variation);
+        self.current_path[ply] = 0;
+        self.update_cache();
+        true
+    }
+
+    /// Removes the variation holding the current move, going back to where it branched off.
+    /// Returns whether the current move was in a variation.
+    pub(super) fn delete_variation(&mut self) -> bool {
+        let Some(ply) = self.get_variation_start() else {
+            return false;
+        };
+        let i = self.current_path[ply];
+        self.current_path.truncate(ply);
+        let parent = self.current_path.clone();
+        self.get_children_mut(&parent).unwrap().remove(i);
+        self.update_cache();
+        true
+    }
+
+    /// Removes the last `n` moves of the mainline, with the variations replacing them, and goes
+    /// to its new end. Returns whether there were enough moves.
+    pub(super) fn take_back(&mut self, n: usize) -> bool {
+        let mut path = self.get_mainline_path();
+        if n == 0 || n > path.len() {
+            return false;
+        }
+        path.truncate(path.len() - n);
+        self.get_children_mut(&path).unwrap().clear();
+        self.current_path = path;
+        self.update_cache();
+        true
     }
 
     pub(super) fn is_replaying(&self) -> bool {
-        self.current_turn_index != self.turns.len()
+        self.replaying
     }
 
+    /// The 1-based index of the round holding the current move, or 0 before the first move.
     pub(super) fn get_current_round(&self) -> usize {
-        (self.current_turn_index + 1) / 2
+        match self.current_path.len() {
+            0 => 0,
+            i => (i + (self.initial_state.player == Color::Black) as usize).div_ceil(2),
+        }
+    }
+
+    pub(super) fn get_initial_state(&self) -> &BoardState {
+        &self.initial_state
+    }
+
+    pub(super) fn get_initial_fen(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.initial_state.get_fen_str(),
+            self.initial_fifty_move_count,
+            self.initial_fullmove_number
+        )
+    }
+
+    pub(super) fn get_initial_fullmove_number(&self) -> usize {
+        self.initial_fullmove_number
+    }
+
+    /// Whether the game started from the standard initial position.
+    pub(super) fn has_standard_start(&self) -> bool {
+        let mut standard_start = BoardState::default();
+        standard_start.variant = self.initial_state.variant;
+        self.initial_state == standard_start
+            && self.initial_fifty_move_count == 0
+            && self.initial_fullmove_number == 1
+    }
+
+    /// The FEN halfmove clock after the move at `path`.
+    fn get_halfmove_clock_at(&self, path: &[usize]) -> u32 {
+        let turns = self.get_line(path);
+        match turns.iter().rposition(|turn| {
+            turn.piece_captured
+                || turn.board_state.get_piece(&turn.mv.to)
+                    == Some(Piece::Pawn(!turn.board_state.player))
+                || turn.mv.promotion.is_some()
+        }) {
+            Some(i) => (turns.len() - i - 1) as u32,
+            None => self.initial_fifty_move_count + turns.len() as u32,
+        }
+    }
+
+    /// The FEN halfmove clock of the current state.
+    pub(super) fn get_halfmove_clock(&self) -> u32 {
+        self.get_halfmove_clock_at(&self.current_path)
+    }
+
+    /// The FEN fullmove number of the state after `ply` moves.
+    pub(super) fn get_fullmove_number_at(&self, ply: usize) -> usize {
+        let initial_ply = (self.initial_state.player == Color::Black) as usize;
+        self.initial_fullmove_number + (initial_ply + ply) / 2
+    }
+
+    /// The FEN fullmove number of the current state.
+    pub(super) fn get_fullmove_number(&self) -> usize {
+        self.get_fullmove_number_at(self.current_path.len())
     }
 }
 
 impl Default for History {
     fn default() -> Self {
-        let (turns, current_turn_index, fifty_move_count, initial_state) = Default::default();
         Self {
-            turns,
-            current_turn_index,
-            fifty_move_count,
-            initial_state,
-            repetition_counter: vec![(BoardState::default(), 1)].into_iter().collect(),
+            first_moves: vec![],
+            current_path: vec![],
+            current_state: BoardState::default(),
+            mainline_len: 0,
+            real_state: BoardState::default(),
+            replaying: false,
+            initial_state: BoardState::default(),
+            initial_annotation: Annotation::default(),
+            initial_fifty_move_count: 0,
+            initial_fullmove_number: 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(history: &mut History, lan: &str) -> bool {
+        let mv = Move::from_lan(lan).unwrap();
+        let mut next_state = *history.get_current_state();
+        next_state.move_piece(&mv);
+        history.add_info(next_state, mv)
+    }
+
+    /// Whether the cached states match the ones found by walking the tree.
+    fn is_cache_valid(history: &History) -> bool {
+        let mut mainline_path = vec![];
+        while history
+            .get_children(&mainline_path)
+            .is_some_and(|children| !children.is_empty())
+        {
+            mainline_path.push(0);
+        }
+        history.get_current_state() == history.get_board_state(&history.current_path)
+            && history.get_real_state() == history.get_board_state(&mainline_path)
+            && history.is_replaying() == (history.current_path != mainline_path)
+    }
+
+    #[test]
+    fn test_variations() {
+        let mut history = History::default();
+        for lan in ["e2e4", "e7e5", "g1f3"] {
+            play(&mut history, lan);
+        }
+        history.previous_move();
+        history.previous_move();
+        // the mainline is kept
+        assert!(play(&mut history, "c7c5"));
+        assert!(play(&mut history, "g1f3"));
+        assert_eq!(history.get_current_path(), [0, 1, 0]);
+        assert_eq!(history.get_mainline().len(), 3);
+        assert!(history.is_replaying());
+        assert!(is_cache_valid(&history));
+
+        // playing a known move follows it
+        history.go_to(&[0]);
+        assert!(!play(&mut history, "e7e5"));
+        assert_eq!(history.get_current_path(), [0, 0]);
+        assert!(history.go_to_sibling(true));
+        assert!(!history.go_to_sibling(true));
+        assert_eq!(history.get_current_path(), [0, 1]);
+        assert!(is_cache_valid(&history));
+        history.next_move();
+
+        assert!(history.promote_variation());
+        assert_eq!(history.get_current_path(), [0, 0, 0]);
+        assert!(!history.is_replaying());
+        assert!(!history.promote_variation());
+        assert_eq!(
+            history.get_real_state().get_piece(&"c5".parse().unwrap()),
+            Some(Piece::Pawn(Color::Black))
+        );
+        assert!(is_cache_valid(&history));
+
+        history.go_to(&[0, 1, 0]);
+        assert!(history.delete_variation());
+        assert_eq!(history.get_current_path(), [0]);
+        assert!(is_cache_valid(&history));
+        assert_eq!(history.get_children(&[0]).unwrap().len(), 1);
+        assert!(!history.go_to(&[0, 1]));
+    }
+
+    #[test]
+    fn test_repetition_count() {
+        let mut history = History::default();
+        for lan in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            play(&mut history, lan);
+        }
+        assert_eq!(history.get_repetition_count(history.get_current_path()), 2);
+        // a variation does not count the positions of the line it left
+        history.go_to(&[0, 0]);
+        for lan in ["b1c3", "f6g8", "c3b1", "g8f6"] {
+            play(&mut history, lan);
+        }
+        assert_eq!(history.get_repetition_count(&[0, 0, 1, 0, 0, 0]), 2);
+        assert_eq!(history.get_fifty_move_count(&[0, 0, 1, 0, 0, 0]), 3);
+        assert_eq!(history.get_repetition_count(&[0, 0, 0, 0]), 2);
+
+        // the counts go down with the moves taken back
+        history.resume();
+        assert!(history.take_back(3));
+        assert_eq!(history.get_current_path(), [0]);
+        assert!(is_cache_valid(&history));
+        assert_eq!(history.get_repetition_count(&[0]), 1);
+        assert_eq!(history.get_fifty_move_count(&[0]), 0);
+        assert!(history.get_children(&[0]).unwrap().is_empty());
+        assert!(!history.take_back(2));
+    }
+
+    #[test]
+    fn test_long_halfmove_clock() {
+        let mut history = History::default();
+        for lan in ["g1f3", "g8f6", "f3g1", "f6g8"].iter().cycle().take(300) {
+            play(&mut history, lan);
         }
+        assert_eq!(history.get_halfmove_clock(), 300);
     }
 }
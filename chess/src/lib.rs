@@ -1,23 +1,52 @@
+mod annotation;
+mod attacks;
+mod bitboard;
 mod board;
 mod board_state;
 mod castling_rights;
+mod clock;
 mod color;
 mod displacement;
+mod engine;
+mod evaluation;
+mod fen;
 mod game;
+mod game_result;
 mod game_status;
 mod history;
+mod move_generation;
+mod move_time;
 mod moves;
+mod perft;
+mod pgn;
 mod piece;
 mod player;
+mod pocket;
 mod position;
 mod result;
 mod round_info;
+mod san;
+#[cfg(test)]
+mod test_utils;
+mod time_control;
 mod timer;
+mod transposition_table;
 mod turn;
+mod variant;
+mod zobrist;
 
+pub use annotation::*;
+pub use clock::*;
 pub use color::*;
+pub use engine::*;
 pub use game::*;
+pub use game_result::*;
+pub use move_time::*;
 pub use moves::*;
 pub use piece::*;
 pub use player::*;
 pub use position::*;
+pub use result::*;
+pub use san::*;
+pub use time_control::*;
+pub use variant::*;
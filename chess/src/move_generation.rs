@@ -0,0 +1,137 @@
+use crate::{
+    attacks, bitboard::Bitboard, board_state::BoardState, castling_rights::CastlingSide,
+    color::Color, displacement::Displacement, moves::Move, piece::Piece, position::Position,
+};
+
+type GetAttacks = fn(&Position, Bitboard) -> Bitboard;
+
+impl BoardState {
+    /// Every legal move of the player to move.
+    pub(super) fn get_legal_moves(&self) -> Vec<Move> {
+        if self.get_variant_status().is_some() {
+            return vec![];
+        }
+        let mut moves = Vec::with_capacity(64);
+        self.add_pawn_moves(&mut moves);
+        self.add_piece_moves(&mut moves);
+        self.add_castling_moves(&mut moves);
+        self.add_drop_moves(&mut moves);
+        moves.retain(|mv| !self.moves_into_check(mv));
+        moves
+    }
+
+    fn moves_into_check(&self, mv: &Move) -> bool {
+        let mut next_state = *self;
+        next_state.move_piece(mv);
+        // blowing up the opponent's king in Atomic wins even if the move leaves the own king
+        // attacked, but not if it blows up both
+        next_state.get_pieces(Piece::King(self.player)).is_empty()
+            || (!next_state.get_pieces(Piece::King(!self.player)).is_empty()
+                && next_state.is_king_attacked(self.player))
+    }
+
+    fn add_pawn_move(&self, moves: &mut Vec<Move>, from: Position, to: Position) {
+        if self.can_promote_piece(Piece::Pawn(self.player), &to) {
+            for piece in Piece::get_promotion_pieces(self.player) {
+                moves.push(Move::with_promotion(from, to, piece));
+            }
+        } else {
+            moves.push(Move::new(from, to));
+        }
+    }
+
+    fn add_pawn_moves(&self, moves: &mut Vec<Move>) {
+        let occupied = self.get_all_occupied();
+        let mut targets = self.get_occupied(!self.player);
+        if let Some(en_passant_position) = self.en_passant_position {
+            targets |= Bitboard::from_position(&en_passant_position);
+        }
+        let advance = Displacement::get_pawn_advance_vector(self.player);
+        let start_rank = match self.player {
+            Color::White => 1,
+            Color::Black => 6,
+        };
+
+        for from in self.get_pieces(Piece::Pawn(self.player)) {
+            let to = from + advance;
+            if !occupied.contains(&to) {
+                self.add_pawn_move(moves, from, to);
+                let double_move = to + advance;
+                if from.y == start_rank && !occupied.contains(&double_move) {
+                    moves.push(Move::new(from, double_move));
+                }
+            }
+            for to in attacks::get_pawn_attacks(&from, self.player) & targets {
+                self.add_pawn_move(moves, from, to);
+            }
+        }
+    }
+
+    fn add_piece_moves(&self, moves: &mut Vec<Move>) {
+        let occupied = self.get_all_occupied();
+        let not_own = !self.get_occupied(self.player);
+        let player = self.player;
+        let pieces: [(Piece, GetAttacks); 5] = [
+            (Piece::Knight(player), |at, _| {
+                attacks::get_knight_attacks(at)
+            }),
+            (Piece::Bishop(player), attacks::get_bishop_attacks),
+            (Piece::Rook(player), attacks::get_rook_attacks),
+            (Piece::Queen(player), attacks::get_queen_attacks),
+            (Piece::King(player), |at, _| attacks::get_king_attacks(at)),
+        ];
+        for (piece, get_attacks) in pieces {
+            for from in self.get_pieces(piece) {
+                for to in get_attacks(&from, occupied) & not_own {
+                    moves.push(Move::new(from, to));
+                }
+            }
+        }
+    }
+
+    fn add_castling_moves(&self, moves: &mut Vec<Move>) {
+        if self.is_in_check() {
+            return;
+        }
+        let king = self.get_king_position(self.player);
+        for side in CastlingSide::ALL {
+            let (Some(rook), Some(target)) = (
+                self.castling_rights.get_rook_position(self.player, side),
+                self.get_castling_target(side),
+            ) else {
+                continue;
+            };
+            let (king_destination, rook_destination) = side.get_destinations(self.player);
+            // every square the king and rook pass over or land on must be empty but for the two
+            // of them, and the squares the king passes over must not be attacked; whether it may
+            // land on its destination is checked with the rook in place like any other move
+            let others = self.get_all_occupied()
+                & !(Bitboard::from_position(&king) | Bitboard::from_position(&rook));
+            let path = Bitboard::from_rank_span(king.y, king.x, king_destination.x)
+                | Bitboard::from_rank_span(rook.y, rook.x, rook_destination.x);
+            let path_is_safe =
+                Bitboard::from_rank_span(king.y, king.x, king_destination.x).all(|at| {
+                    at == king || at == king_destination || self.is_safe_for_king(&at, self.player)
+                });
+            if (path & others).is_empty() && path_is_safe {
+                moves.push(Move::new(king, target));
+            }
+        }
+    }
+
+    /// Drops of the pieces in the player's pocket onto empty squares, pawns not on the first or
+    /// last rank.
+    fn add_drop_moves(&self, moves: &mut Vec<Move>) {
+        let empty = !self.get_all_occupied();
+        for (piece, _) in self.pockets.get_pieces(self.player) {
+            let targets = if piece.is_pawn() {
+                empty & !Bitboard::BACK_RANKS
+            } else {
+                empty
+            };
+            for to in targets {
+                moves.push(Move::with_drop(piece, to));
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use web_time::Duration;
+
+use crate::color::Color;
+
+/// The players' clocks right after a move, and how long the move took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveTime {
+    pub white_time: Duration,
+    pub black_time: Duration,
+    pub time_spent: Duration,
+}
+
+impl MoveTime {
+    /// The time left on the clock of `player`.
+    pub fn get_time(&self, player: Color) -> Duration {
+        match player {
+            Color::White => self.white_time,
+            Color::Black => self.black_time,
+        }
+    }
+}
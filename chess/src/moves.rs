@@ -2,35 +2,96 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{piece::Piece, position::Position};
+use crate::{color::Color, piece::Piece, position::Position};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
+    pub promotion: Option<Piece>,
+    /// The piece dropped from the pocket onto `to` in Crazyhouse, with `from` equal to `to`.
+    pub drop: Option<Piece>,
 }
 
 impl Move {
     pub fn new(from: Position, to: Position) -> Self {
-        Self { from, to }
+        Self {
+            from,
+            to,
+            promotion: None,
+            drop: None,
+        }
+    }
+
+    pub fn with_promotion(from: Position, to: Position, piece: Piece) -> Self {
+        Self {
+            from,
+            to,
+            promotion: Some(piece),
+            drop: None,
+        }
+    }
+
+    pub fn with_drop(piece: Piece, to: Position) -> Self {
+        Self {
+            from: to,
+            to,
+            promotion: None,
+            drop: Some(piece),
+        }
     }
 
     pub fn get_positions(&self) -> [Position; 2] {
         [self.from, self.to]
     }
 
-    // creates move from the "long algebraic notation" that stockfish uses
+    // creates move from the "long algebraic notation" that stockfish uses, e.g. `e2e4` or `e7e8n`,
+    // or a drop like `N@f3`, with a lowercase letter for black pieces
     pub fn from_lan(lan: &str) -> Option<Self> {
-        Some(Self::new(
-            Position::new(
-                lan.chars().nth(0)? as usize - 'a' as usize,
-                lan.chars().nth(1)? as usize - '1' as usize,
-            ),
-            Position::new(
-                lan.chars().nth(2)? as usize - 'a' as usize,
-                lan.chars().nth(3)? as usize - '1' as usize,
-            ),
-        ))
+        if lan.get(1..2)? == "@" {
+            let piece = lan
+                .chars()
+                .next()
+                .and_then(Piece::from_fen_char)
+                .filter(|piece| !matches!(piece, Piece::King(..)))?;
+            return Some(Self::with_drop(piece, lan.get(2..)?.parse().ok()?));
+        }
+        let from = lan.get(0..2)?.parse().ok()?;
+        let to: Position = lan.get(2..4)?.parse().ok()?;
+        let promotion = match lan.get(4..)? {
+            "" => None,
+            piece => {
+                let player = if to.y == 7 {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                let piece = match piece {
+                    "n" => Piece::Knight(player),
+                    "b" => Piece::Bishop(player),
+                    "r" => Piece::Rook(player),
+                    "q" => Piece::Queen(player),
+                    _ => return None,
+                };
+                Some(piece)
+            }
+        };
+        Some(Self {
+            from,
+            to,
+            promotion,
+            drop: None,
+        })
+    }
+
+    pub fn to_lan(&self) -> String {
+        if let Some(piece) = self.drop {
+            return format!("{}@{}", piece.get_fen_char(), self.to);
+        }
+        let promotion = self.promotion.map_or(String::new(), |piece| {
+            piece.get_fen_char().to_ascii_lowercase().to_string()
+        });
+        format!("{}{}{}", self.from, self.to, promotion)
     }
 
     pub fn to_str(&self, piece: Piece) -> String {
@@ -40,6 +101,55 @@ impl Move {
 
 impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{} -> {}", self.from, self.to)
+        if let Some(piece) = self.drop {
+            return write!(f, "{piece}@{}", self.to);
+        }
+        write!(f, "{} -> {}", self.from, self.to)?;
+        if let Some(piece) = self.promotion {
+            write!(f, "={piece}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_lan() {
+        assert_eq!(
+            Move::from_lan("e2e4"),
+            Some(Move::new(Position::new(4, 1), Position::new(4, 3)))
+        );
+        assert_eq!(
+            Move::from_lan("e7e8n"),
+            Some(Move::with_promotion(
+                Position::new(4, 6),
+                Position::new(4, 7),
+                Piece::Knight(Color::White)
+            ))
+        );
+        assert_eq!(
+            Move::from_lan("a2b1r"),
+            Some(Move::with_promotion(
+                Position::new(0, 1),
+                Position::new(1, 0),
+                Piece::Rook(Color::Black)
+            ))
+        );
+        assert_eq!(Move::from_lan("e7e8n").unwrap().to_lan(), "e7e8n");
+        assert_eq!(Move::from_lan("e7e8k"), None);
+        assert_eq!(Move::from_lan("e7"), None);
+        assert_eq!(Move::from_lan("z1e2"), None);
+        assert_eq!(
+            Move::from_lan("n@f6"),
+            Some(Move::with_drop(
+                Piece::Knight(Color::Black),
+                Position::new(5, 5)
+            ))
+        );
+        assert_eq!(Move::from_lan("P@e4").unwrap().to_lan(), "P@e4");
+        assert_eq!(Move::from_lan("K@e4"), None);
     }
 }
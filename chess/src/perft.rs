@@ -0,0 +1,42 @@
+use crate::{board_state::BoardState, game::Game, moves::Move};
+
+impl Game {
+    /// Counts the leaf nodes of the legal move tree `depth` plies deep from the current position.
+    pub fn perft(&self, depth: usize) -> u64 {
+        Self::perft_state(*self.get_current_state(), depth)
+    }
+
+    /// The perft node count below each legal move of the current position, sorted by move.
+    pub fn divide(&self, depth: usize) -> Vec<(Move, u64)> {
+        let state = *self.get_current_state();
+        let mut counts: Vec<_> = state
+            .get_legal_moves()
+            .into_iter()
+            .map(|mv| {
+                let mut next_state = state;
+                next_state.move_piece(&mv);
+                (mv, Self::perft_state(next_state, depth.saturating_sub(1)))
+            })
+            .collect();
+        counts.sort_by_key(|(mv, _)| mv.to_lan());
+        counts
+    }
+
+    fn perft_state(state: BoardState, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = state.get_legal_moves();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .into_iter()
+            .map(|mv| {
+                let mut next_state = state;
+                next_state.move_piece(&mv);
+                Self::perft_state(next_state, depth - 1)
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,826 @@
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+use web_time::Duration;
+
+use crate::{
+    annotation::{Annotation, MarkupArrow, MarkupColor, MarkupSquare},
+    game::Game,
+    game_result::Outcome,
+    moves::Move,
+    position::Position,
+    result::ChessError,
+    variant::Variant,
+};
+
+const MAX_LINE_LENGTH: usize = 80;
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+/// A move of the movetext with its NAGs, the comment after it and the variations replacing it.
+pub(super) struct PgnMove {
+    pub(super) san: String,
+    pub(super) nags: Vec<u8>,
+    pub(super) comment: Option<String>,
+    pub(super) variations: Vec<Vec<PgnMove>>,
+}
+
+/// A game in the PGN export format.
+pub(super) struct Pgn {
+    tags: Vec<(String, String)>,
+    /// The comment before the first move.
+    comment: Option<String>,
+    moves: Vec<PgnMove>,
+    first_fullmove_number: usize,
+    black_moves_first: bool,
+}
+
+impl Pgn {
+    pub(super) fn new(result: &str) -> Self {
+        let mut pgn = Self {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            comment: None,
+            moves: Vec::new(),
+            first_fullmove_number: 1,
+            black_moves_first: false,
+        };
+        pgn.set_tag("Result", result);
+        pgn
+    }
+
+    /// Replaces the value of the tag, or adds it after the existing tags.
+    pub(super) fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub(super) fn set_moves(
+        &mut self,
+        moves: Vec<PgnMove>,
+        first_fullmove_number: usize,
+        black_moves_first: bool,
+    ) {
+        self.moves = moves;
+        self.first_fullmove_number = first_fullmove_number;
+        self.black_moves_first = black_moves_first;
+    }
+
+    pub(super) fn set_comment(&mut self, comment: Option<String>) {
+        self.comment = comment;
+    }
+
+    fn get_result(&self) -> &str {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == "Result")
+            .map_or("*", |(_, value)| value)
+    }
+
+    /// Adds the tokens of `moves`, starting at `first_ply` counted from white's first move.
+    /// Variations are wrapped in parentheses glued to their first and last tokens.
+    fn push_line_tokens(&self, tokens: &mut Vec<String>, moves: &[PgnMove], first_ply: usize) {
+        let mut needs_number = true;
+        for (i, mv) in moves.iter().enumerate() {
+            let ply = first_ply + i;
+            let fullmove_number = self.first_fullmove_number + ply / 2;
+            if ply.is_multiple_of(2) {
+                tokens.push(format!("{fullmove_number}."));
+            } else if needs_number {
+                tokens.push(format!("{fullmove_number}..."));
+            }
+            tokens.push(mv.san.clone());
+            tokens.extend(mv.nags.iter().map(|nag| format!("${nag}")));
+            needs_number = false;
+            if let Some(comment) = &mv.comment {
+                tokens.push(format!("{{{comment}}}"));
+                needs_number = true;
+            }
+            for variation in &mv.variations {
+                let start = tokens.len();
+                self.push_line_tokens(tokens, variation, ply);
+                tokens[start].insert(0, '(');
+                tokens.last_mut().unwrap().push(')');
+                needs_number = true;
+            }
+        }
+    }
+
+    fn get_movetext_tokens(&self) -> Vec<String> {
+        let mut tokens = Vec::with_capacity(self.moves.len() * 3 / 2 + 2);
+        if let Some(comment) = &self.comment {
+            tokens.push(format!("{{{comment}}}"));
+        }
+        self.push_line_tokens(&mut tokens, &self.moves, self.black_moves_first as usize);
+        tokens.push(self.get_result().to_string());
+        tokens
+    }
+}
+
+impl fmt::Display for Pgn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{name} \"{value}\"]")?;
+        }
+        writeln!(f)?;
+
+        let mut line_length = 0;
+        for token in self.get_movetext_tokens() {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{token}")?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Tag(&'a str, String),
+    Move(&'a str),
+    Nag(u8),
+    Comment(&'a str),
+    VariationStart,
+    VariationEnd,
+    Result(&'a str),
+}
+
+/// Splits PGN text into tokens, dropping move numbers.
+struct Lexer<'a> {
+    pgn: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(pgn: &'a str) -> Self {
+        Self {
+            pgn,
+            chars: pgn.char_indices().peekable(),
+        }
+    }
+
+    fn skip_until(&mut self, end: char) -> bool {
+        self.chars.by_ref().any(|(_, c)| c == end)
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.chars.peek().map_or(self.pgn.len(), |&(i, _)| i);
+        while self.chars.next_if(|&(_, c)| predicate(c)).is_some() {}
+        let end = self.chars.peek().map_or(self.pgn.len(), |&(i, _)| i);
+        &self.pgn[start..end]
+    }
+
+    fn read_tag(&mut self) -> Result<Token<'a>, ChessError> {
+        let invalid = || ChessError::PgnSyntax("malformed tag".to_string());
+        self.take_while(char::is_whitespace);
+        let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+        self.take_while(char::is_whitespace);
+        if name.is_empty() || self.chars.next().map(|(_, c)| c) != Some('"') {
+            return Err(invalid());
+        }
+        let mut value = String::new();
+        loop {
+            match self.chars.next().ok_or_else(invalid)?.1 {
+                '"' => break,
+                '\\' => value.push(self.chars.next().ok_or_else(invalid)?.1),
+                c => value.push(c),
+            }
+        }
+        self.take_while(char::is_whitespace);
+        match self.chars.next() {
+            Some((_, ']')) => Ok(Token::Tag(name, value)),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn is_line_start(&self, i: usize) -> bool {
+        i == 0 || self.pgn[..i].ends_with('\n')
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, ChessError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.take_while(char::is_whitespace);
+            let &(i, c) = self.chars.peek()?;
+            let token = match c {
+                '%' if self.is_line_start(i) => {
+                    self.skip_until('\n');
+                    continue;
+                }
+                ';' => {
+                    self.chars.next();
+                    Token::Comment(self.take_while(|c| c != '\n'))
+                }
+                '{' => {
+                    self.chars.next();
+                    let comment = self.take_while(|c| c != '}');
+                    if self.chars.next().is_none() {
+                        return Some(Err(ChessError::PgnSyntax(
+                            "unterminated comment".to_string(),
+                        )));
+                    }
+                    Token::Comment(comment)
+                }
+                '[' => {
+                    self.chars.next();
+                    return Some(self.read_tag());
+                }
+                '(' => {
+                    self.chars.next();
+                    Token::VariationStart
+                }
+                ')' => {
+                    self.chars.next();
+                    Token::VariationEnd
+                }
+                '$' => {
+                    self.chars.next();
+                    match self.take_while(|c| c.is_ascii_digit()).parse() {
+                        Ok(nag) => Token::Nag(nag),
+                        Err(_) => {
+                            return Some(Err(ChessError::PgnSyntax("invalid NAG".to_string())))
+                        }
+                    }
+                }
+                _ => {
+                    let symbol = self.take_while(|c| !c.is_whitespace() && !"{}()[];$".contains(c));
+                    if symbol.is_empty() {
+                        return Some(Err(ChessError::PgnSyntax(format!("unexpected '{c}'"))));
+                    }
+                    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*") {
+                        Token::Result(symbol)
+                    } else {
+                        // move numbers like `12.` or `12...` may be glued to the move
+                        match symbol.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.') {
+                            "" => continue,
+                            san => Token::Move(san),
+                        }
+                    }
+                }
+            };
+            return Some(Ok(token));
+        }
+    }
+}
+
+/// A game being read.
+struct Reader {
+    game: Game,
+    /// The moves to go back to at the end of each open variation.
+    variation_ends: Vec<Vec<usize>>,
+}
+
+impl Reader {
+    /// Plays the move of a SAN token, which may end with a glyph like `!?`.
+    fn play(&mut self, san: &str, game_number: usize) -> Result<(), ChessError> {
+        let ply = self.game.get_current_path().len() + 1;
+        let error = |source| ChessError::PgnMove {
+            game: game_number,
+            ply,
+            token: san.to_string(),
+            source: Box::new(source),
+        };
+        let mv = Move::from_san(&self.game, san).map_err(error)?;
+        self.game.move_piece(mv).map_err(error)?;
+        // the clocks of the import itself mean nothing, only `%clk` comments count
+        self.game.clear_move_time();
+        let glyph = &san[san.trim_end_matches(['!', '?']).len()..];
+        if let Some(nag) = Annotation::get_glyph_nag(glyph) {
+            self.add_nag(nag);
+        }
+        Ok(())
+    }
+
+    fn add_nag(&mut self, nag: u8) {
+        let mut annotation = self.game.get_annotation().clone();
+        if !annotation.nags.contains(&nag) {
+            annotation.nags.push(nag);
+            self.game.set_annotation(annotation);
+        }
+    }
+
+    /// Adds the text and the markup of a comment to the annotation of the current position,
+    /// and the clock of a `%clk` command to its move.
+    fn add_comment(&mut self, comment: &str) {
+        let (commands, text) = split_commands(comment);
+        let mut annotation = self.game.get_annotation().clone();
+        for (name, args) in commands {
+            match name {
+                "clk" => {
+                    if let Some(remaining) = parse_clock(args) {
+                        self.game.set_clock(remaining);
+                    }
+                }
+                "cal" => annotation
+                    .arrows
+                    .extend(args.split(',').filter_map(parse_arrow)),
+                "csl" => annotation
+                    .squares
+                    .extend(args.split(',').filter_map(parse_square)),
+                _ => (),
+            }
+        }
+        if !text.is_empty() {
+            if !annotation.comment.is_empty() {
+                annotation.comment.push(' ');
+            }
+            annotation.comment.push_str(&text);
+        }
+        self.game.set_annotation(annotation);
+    }
+
+    /// Starts a variation that replaces the last move.
+    fn start_variation(&mut self) -> Result<(), ChessError> {
+        let path = self.game.get_current_path().to_vec();
+        let Some((_, parent)) = path.split_last() else {
+            return Err(ChessError::PgnSyntax(
+                "variation before the first move".to_string(),
+            ));
+        };
+        self.game.go_to_move(parent)?;
+        self.variation_ends.push(path);
+        Ok(())
+    }
+
+    fn end_variation(&mut self) -> Result<(), ChessError> {
+        let path = self
+            .variation_ends
+            .pop()
+            .ok_or_else(|| ChessError::PgnSyntax("unmatched ')'".to_string()))?;
+        self.game.go_to_move(&path)
+    }
+
+    /// Ends the game with `result`, the result token or else the `Result` tag.
+    fn finish(mut self, result: Option<&str>) -> Result<Game, ChessError> {
+        if !self.variation_ends.is_empty() {
+            return Err(ChessError::PgnSyntax("unterminated variation".to_string()));
+        }
+        self.game.resume();
+        self.game.stop_timer();
+        let outcome = match result {
+            Some("1-0") => Some(Outcome::WhiteWins),
+            Some("0-1") => Some(Outcome::BlackWins),
+            Some("1/2-1/2") => Some(Outcome::Draw),
+            _ => None,
+        };
+        if let Some(outcome) = outcome {
+            self.game.set_recorded_result(outcome)?;
+        }
+        Ok(self.game)
+    }
+}
+
+/// The `[%clk h:mm:ss]` command of a move comment, with whole seconds.
+fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!(
+        "[%clk {}:{:02}:{:02}]",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// The comment of a position: the `%clk`, `%cal` and `%csl` commands followed by the text, or
+/// `None` if there is nothing to write.
+pub(super) fn format_comment(clock: Option<Duration>, annotation: &Annotation) -> Option<String> {
+    let mut parts = vec![];
+    parts.extend(clock.map(format_clock));
+    if !annotation.arrows.is_empty() {
+        let arrows: Vec<_> = annotation
+            .arrows
+            .iter()
+            .map(|arrow| format!("{}{}{}", arrow.color.get_char(), arrow.from, arrow.to))
+            .collect();
+        parts.push(format!("[%cal {}]", arrows.join(",")));
+    }
+    if !annotation.squares.is_empty() {
+        let squares: Vec<_> = annotation
+            .squares
+            .iter()
+            .map(|square| format!("{}{}", square.color.get_char(), square.position))
+            .collect();
+        parts.push(format!("[%csl {}]", squares.join(",")));
+    }
+    if !annotation.comment.is_empty() {
+        // a comment ends at the first closing brace
+        parts.push(annotation.comment.replace('}', ""));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Splits a comment into its `[%name args]` commands and the text around them, with the
+/// whitespace collapsed.
+fn split_commands(comment: &str) -> (Vec<(&str, &str)>, String) {
+    let mut commands = vec![];
+    let mut text = String::new();
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        let Some(length) = rest[start..].find(']') else {
+            break;
+        };
+        let command = &rest[start + 2..start + length];
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        commands.push((name, args.trim()));
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        rest = &rest[start + length + 1..];
+    }
+    text.push_str(rest);
+    (
+        commands,
+        text.split_whitespace().collect::<Vec<_>>().join(" "),
+    )
+}
+
+/// Reads an arrow of a `%cal` command, e.g. `Gf1c4`.
+fn parse_arrow(arrow: &str) -> Option<MarkupArrow> {
+    let color = MarkupColor::from_char(arrow.chars().next()?)?;
+    let from: Position = arrow.get(1..3)?.parse().ok()?;
+    let to: Position = arrow.get(3..)?.parse().ok()?;
+    Some(MarkupArrow { from, to, color })
+}
+
+/// Reads a square of a `%csl` command, e.g. `Rd4`.
+fn parse_square(square: &str) -> Option<MarkupSquare> {
+    let color = MarkupColor::from_char(square.chars().next()?)?;
+    let position = square.get(1..)?.parse().ok()?;
+    Some(MarkupSquare { position, color })
+}
+
+/// Reads the time of the arguments of a `%clk` command, `h:mm:ss`. The seconds may have a
+/// fraction.
+fn parse_clock(clock: &str) -> Option<Duration> {
+    let mut fields = clock.split(':');
+    let (Some(hours), Some(minutes), Some(seconds), None) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return None;
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    if minutes >= 60 || !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+fn get_tag<'a>(tags: &'a [(&str, String)], tag: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|(name, _)| *name == tag)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Reads every game in PGN text, with its tags, variations and result.
+pub(super) fn parse_games(pgn: &str) -> Result<Vec<Game>, ChessError> {
+    let mut games = vec![];
+    let mut tags: Vec<(&str, String)> = vec![];
+    let mut current: Option<Reader> = None;
+    // comments between games, which only belong to a game if its moves follow
+    let mut pending_comments = vec![];
+
+    for token in Lexer::new(pgn) {
+        let token = token?;
+        if let Token::Tag(name, value) = token {
+            if let Some(reader) = current.take() {
+                games.push(reader.finish(get_tag(&tags, "Result"))?);
+                tags.clear();
+            }
+            pending_comments.clear();
+            tags.push((name, value));
+            continue;
+        }
+        if let (None, Token::Comment(comment)) = (&current, &token) {
+            pending_comments.push(*comment);
+            continue;
+        }
+        let reader = match &mut current {
+            Some(reader) => reader,
+            None => {
+                let get_tag = |tag| get_tag(&tags, tag);
+                let variant = match get_tag("Variant") {
+                    Some(name) => name.parse()?,
+                    None => Variant::Standard,
+                };
+                let mut builder = Game::builder().variant(variant);
+                // unknown, untimed and unsupported time controls keep the default
+                if let Some(time_control) = get_tag("TimeControl").and_then(|tag| tag.parse().ok())
+                {
+                    builder = builder.time_control(time_control);
+                }
+                if let Some(fen) = get_tag("FEN") {
+                    builder = builder.fen(fen)?;
+                }
+                let mut game = builder.build()?;
+                game.set_tags(
+                    tags.iter()
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect(),
+                );
+                let reader = current.insert(Reader {
+                    game,
+                    variation_ends: vec![],
+                });
+                for comment in pending_comments.drain(..) {
+                    reader.add_comment(comment);
+                }
+                reader
+            }
+        };
+        match token {
+            Token::Move(san) => reader.play(san, games.len() + 1)?,
+            Token::VariationStart => reader.start_variation()?,
+            Token::VariationEnd => reader.end_variation()?,
+            Token::Result(result) => {
+                if let Some(reader) = current.take() {
+                    games.push(reader.finish(Some(result))?);
+                }
+                tags.clear();
+            }
+            Token::Comment(comment) => reader.add_comment(comment),
+            Token::Nag(nag) => reader.add_nag(nag),
+            Token::Tag(..) => (),
+        }
+    }
+    if let Some(reader) = current {
+        games.push(reader.finish(get_tag(&tags, "Result"))?);
+    }
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::Game,
+        test_utils::{new_game_with_clock, play},
+        time_control::TimeControl,
+    };
+
+    #[test]
+    fn test_to_pgn() {
+        let (mut game, _) = new_game_with_clock(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            None,
+        );
+        play(
+            &mut game,
+            &["e2e4", "e7e5", "d1h5", "b8c6", "f1c4", "g8f6", "h5f7"],
+        );
+        assert_eq!(
+            game.to_pgn_with_tags(&[("White", "Alice"), ("Event", "Casual \"blitz\"")]),
+            "[Event \"Casual \\\"blitz\\\"\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Alice\"]\n\
+             [Black \"?\"]\n\
+             [Result \"1-0\"]\n\
+             [TimeControl \"3600\"]\n\
+             \n\
+             1. e4 {[%clk 1:00:00]} 1... e5 {[%clk 1:00:00]} 2. Qh5 {[%clk 1:00:00]} 2... Nc6\n\
+             {[%clk 1:00:00]} 3. Bc4 {[%clk 1:00:00]} 3... Nf6 {[%clk 1:00:00]} 4. Qxf7#\n\
+             {[%clk 1:00:00]} 1-0\n"
+        );
+    }
+
+    #[test]
+    fn test_to_pgn_from_position() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 3 40";
+        let (mut game, clock) = new_game_with_clock(fen, None);
+        play(&mut game, &["e8d7"]);
+        clock.advance(Duration::from_secs(61));
+        play(&mut game, &["e2e4"]);
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 3 40\"]\n"));
+        assert!(pgn.ends_with("\n\n40... Kd7 {[%clk 1:00:00]} 41. e4 {[%clk 0:58:59]} *\n"));
+    }
+
+    #[test]
+    fn test_parse_games() {
+        let games = Game::from_pgn(
+            "[Event \"Test \\\"quoted\\\"\"]
+[White \"A\"]
+
+1. e4 {best by test} e5 2. Nf3 (2. Qh5 Nc6 (2... g6) 3. Bc4) 2... Nc6 $1 3. Bb5 a6
+; rest of line comment
+4. Ba4 1-0
+
+[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 1\"]
+
+1... Kd7 2. e4 *",
+        )
+        .unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(
+            games[0].get_fen_str(),
+            "r1bqkbnr/1ppp1ppp/p1n5/4p3/B3P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 1 4"
+        );
+        assert_eq!(games[1].get_fen_str(), "8/3k4/8/8/4P3/8/8/4K3 b - e3 0 2");
+        // the tags, variations, comments, NAGs and the result are kept
+        let pgn = games[0].to_pgn();
+        assert_eq!(
+            pgn,
+            "[Event \"Test \\\"quoted\\\"\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"A\"]\n\
+             [Black \"?\"]\n\
+             [Result \"1-0\"]\n\
+             [TimeControl \"3600\"]\n\
+             \n\
+             1. e4 {best by test} 1... e5 2. Nf3 (2. Qh5 Nc6 (2... g6) 3. Bc4) 2... Nc6 $1 3.\n\
+             Bb5 a6 {rest of line comment} 4. Ba4 1-0\n"
+        );
+        assert_eq!(Game::from_pgn(&pgn).unwrap()[0].to_pgn(), pgn);
+        // a result the moves do not decide still ends the game
+        let game = Game::from_pgn("[Result \"1-0\"][White \"Carlsen\"] 1. e4 e5 1-0")
+            .unwrap()
+            .remove(0);
+        assert_eq!(game.result().unwrap().outcome, Outcome::WhiteWins);
+        assert_eq!(
+            game.get_tags()[1],
+            ("White".to_string(), "Carlsen".to_string())
+        );
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[White \"Carlsen\"]"));
+        assert!(pgn.ends_with("\n\n1. e4 e5 1-0\n"));
+
+        let mut game = games.into_iter().next().unwrap();
+        game.go_to_start();
+        game.go_forward_a_move();
+        assert_eq!(
+            game.get_fen_str(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+
+        // a comment after a result opens no game of its own
+        let games =
+            Game::from_pgn("[Event \"a\"]\n\n1. e4 e5 1-0 {after}\n\n[Event \"b\"]\n\n1. d4 *")
+                .unwrap();
+        assert_eq!(games.len(), 2);
+        assert!(games[1].to_pgn().ends_with("\n\n1. d4 *\n"));
+        assert_eq!(Game::from_pgn("1. e4 e5 *\n{trailing}").unwrap().len(), 1);
+        // but one before the moves still belongs to their game
+        let game = Game::from_pgn("1. e4 *\n\n{intro} 1. d4 *")
+            .unwrap()
+            .remove(1);
+        assert!(game.to_pgn().ends_with("\n\n{intro} 1. d4 *\n"));
+    }
+
+    #[test]
+    fn test_parse_games_errors() {
+        let parse = |pgn: &str| Game::from_pgn(pgn).err().unwrap();
+        assert!(matches!(
+            parse("1. e4 e5 *\n\n1. e4 e5 2. Ke3 *"),
+            ChessError::PgnMove { game: 2, ply: 3, ref token, .. } if token == "Ke3"
+        ));
+        assert!(matches!(
+            parse("1. e4 (1. d4 d5 2. Bg4) e5 *"),
+            ChessError::PgnMove {
+                game: 1,
+                ply: 3,
+                ..
+            }
+        ));
+        assert!(matches!(parse("1. e4 (1. d4 *"), ChessError::PgnSyntax(..)));
+        assert!(matches!(parse("1. e4 {open"), ChessError::PgnSyntax(..)));
+        assert!(matches!(
+            parse("[Event \"?\" 1. e4"),
+            ChessError::PgnSyntax(..)
+        ));
+    }
+
+    #[test]
+    fn test_time_control_tag() {
+        let game = Game::from_pgn("[TimeControl \"40/5400:1800+30\"]\n\n1. e4 *")
+            .unwrap()
+            .remove(0);
+        assert_eq!(game.get_time_control().to_string(), "40/5400:1800+30");
+        assert!(game.to_pgn().contains("[TimeControl \"40/5400:1800+30\"]"));
+        // a time control we cannot play keeps the default
+        let game = Game::from_pgn("[TimeControl \"?\"]\n\n1. e4 *")
+            .unwrap()
+            .remove(0);
+        assert_eq!(game.get_time_control(), &TimeControl::default());
+    }
+
+    #[test]
+    fn test_clock_comments() {
+        let (mut game, clock) = new_game_with_clock(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            Some("180+2".parse().unwrap()),
+        );
+        for (lan, seconds) in [("e2e4", 0), ("e7e5", 3), ("g1f3", 10)] {
+            clock.advance(Duration::from_secs(seconds));
+            play(&mut game, &[lan]);
+        }
+        let pgn = game.to_pgn();
+        assert!(pgn.ends_with(
+            "1. e4 {[%clk 0:03:02]} 1... e5 {[%clk 0:02:59]} 2. Nf3 {[%clk 0:02:54]} *\n"
+        ));
+        let imported = Game::from_pgn(&pgn).unwrap().remove(0);
+        assert_eq!(imported.get_move_times(), game.get_move_times());
+
+        // moves without a clock comment have no times
+        let game = Game::from_pgn("1. e4 { [%clk 1:00:00.5] good } e5 *")
+            .unwrap()
+            .remove(0);
+        let times = game.get_move_times();
+        assert_eq!(
+            times[0].map(|time| time.white_time),
+            Some(Duration::from_millis(3600500))
+        );
+        assert_eq!(times[1], None);
+
+        // a clock before the first move belongs to no move
+        let game = Game::from_pgn("{[%clk 0:01:00]} 1. e4 {[%clk 0:00:59]} *")
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            game.get_move_times()[0].map(|time| time.white_time),
+            Some(Duration::from_secs(59))
+        );
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut game = Game::from_pgn(
+            "{Opening lesson} 1. e4!? {[%clk 0:05:00] [%cal Gf1c4,Rd1h5] Aims at f7 [%csl Yf7]} \
+             e5 $2 *",
+        )
+        .unwrap()
+        .remove(0);
+        assert_eq!(game.get_annotation().nags, [2]);
+        game.go_back_a_move();
+        let annotation = game.get_annotation();
+        assert_eq!(annotation.comment, "Aims at f7");
+        assert_eq!(annotation.get_move_glyphs(), "!?");
+        assert_eq!(
+            annotation.arrows[1],
+            MarkupArrow {
+                from: "d1".parse().unwrap(),
+                to: "h5".parse().unwrap(),
+                color: MarkupColor::Red,
+            }
+        );
+        assert_eq!(
+            annotation.squares,
+            [MarkupSquare {
+                position: "f7".parse().unwrap(),
+                color: MarkupColor::Yellow,
+            }]
+        );
+        game.go_to_start();
+        assert_eq!(game.get_annotation().comment, "Opening lesson");
+
+        let pgn = game.to_pgn();
+        assert!(pgn.ends_with(
+            "{Opening lesson} 1. e4 $5\n\
+             {[%clk 0:05:00] [%cal Gf1c4,Rd1h5] [%csl Yf7] Aims at f7} 1... e5 $2 *\n"
+        ));
+        assert_eq!(Game::from_pgn(&pgn).unwrap().remove(0).to_pgn(), pgn);
+    }
+
+    #[test]
+    fn test_movetext_wraps_lines() {
+        let mut pgn = Pgn::new("*");
+        let moves = (0..60)
+            .map(|_| PgnMove {
+                san: "Nf3".to_string(),
+                nags: vec![],
+                comment: None,
+                variations: vec![],
+            })
+            .collect();
+        pgn.set_moves(moves, 1, false);
+        let pgn = pgn.to_string();
+        assert!(pgn.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+        assert!(pgn.ends_with("30. Nf3 Nf3 *\n"));
+    }
+}
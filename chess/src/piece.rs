@@ -1,8 +1,10 @@
 use std::fmt;
 
-use crate::{color::Color, displacement::Displacement};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+use crate::color::Color;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum Piece {
     Pawn(Color),
     Knight(Color),
@@ -17,6 +19,15 @@ impl Piece {
         matches!(self, Piece::Pawn(..))
     }
 
+    pub fn get_promotion_pieces(player: Color) -> [Self; 4] {
+        [
+            Self::Queen(player),
+            Self::Rook(player),
+            Self::Bishop(player),
+            Self::Knight(player),
+        ]
+    }
+
     pub(super) fn get_player(self) -> Color {
         match self {
             Self::Pawn(player)
@@ -28,19 +39,29 @@ impl Piece {
         }
     }
 
-    pub(super) fn get_vectors(self) -> &'static [Displacement] {
+    /// The same kind of piece for `player`.
+    pub(super) fn with_player(self, player: Color) -> Self {
         match self {
-            Self::Pawn(..) => panic!("Try calling `Displacement::get_pawn_*_vector()` instead"),
-            Self::Rook(..) => Displacement::get_rook_vectors(),
-            Self::Bishop(..) => Displacement::get_bishop_vectors(),
-            Self::Knight(..) => Displacement::get_knight_vectors(),
-            Self::Queen(..) => Displacement::get_queen_vectors(),
-            Self::King(..) => Displacement::get_king_vectors(),
+            Self::Pawn(..) => Self::Pawn(player),
+            Self::Knight(..) => Self::Knight(player),
+            Self::Bishop(..) => Self::Bishop(player),
+            Self::Rook(..) => Self::Rook(player),
+            Self::Queen(..) => Self::Queen(player),
+            Self::King(..) => Self::King(player),
         }
     }
 
-    pub(super) fn can_snipe(self) -> bool {
-        matches!(self, Self::Bishop(..) | Self::Rook(..) | Self::Queen(..))
+    /// The index of the piece among the twelve kinds of piece of both colors.
+    pub(super) fn get_index(self) -> usize {
+        let kind = match self {
+            Self::Pawn(..) => 0,
+            Self::Knight(..) => 1,
+            Self::Bishop(..) => 2,
+            Self::Rook(..) => 3,
+            Self::Queen(..) => 4,
+            Self::King(..) => 5,
+        };
+        kind * 2 + self.get_player() as usize
     }
 
     pub(super) fn get_fen_char(&self) -> char {
@@ -57,6 +78,28 @@ impl Piece {
             Color::Black => c,
         }
     }
+
+    /// The uppercase letter used for the piece in SAN, e.g. `N` for knights.
+    pub(super) fn get_san_char(&self) -> char {
+        self.get_fen_char().to_ascii_uppercase()
+    }
+
+    pub(super) fn from_fen_char(c: char) -> Option<Self> {
+        let player = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        match c.to_ascii_lowercase() {
+            'p' => Some(Piece::Pawn(player)),
+            'b' => Some(Piece::Bishop(player)),
+            'n' => Some(Piece::Knight(player)),
+            'r' => Some(Piece::Rook(player)),
+            'k' => Some(Piece::King(player)),
+            'q' => Some(Piece::Queen(player)),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Piece {
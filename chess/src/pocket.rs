@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+use crate::{color::Color, piece::Piece, result::ChessError, zobrist};
+
+/// The most pieces of one kind a pocket may start with in a FEN string.
+const MAX_COUNT: u8 = 16;
+
+/// The captured pieces each player holds in Crazyhouse, which they may drop back onto the board
+/// as their own. Empty in every other variant.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(super) struct Pockets {
+    /// How many pawns, knights, bishops, rooks and queens each player holds.
+    counts: [[u8; 5]; 2],
+}
+
+impl Pockets {
+    /// The player and kind of `piece`, which cannot be a king.
+    fn get_slot(piece: Piece) -> (usize, usize) {
+        let kind = match piece {
+            Piece::Pawn(..) => 0,
+            Piece::Knight(..) => 1,
+            Piece::Bishop(..) => 2,
+            Piece::Rook(..) => 3,
+            Piece::Queen(..) => 4,
+            Piece::King(..) => panic!("a king cannot be put in a pocket"),
+        };
+        (piece.get_player() as usize, kind)
+    }
+
+    pub(super) fn add(&mut self, piece: Piece) {
+        let (player, kind) = Self::get_slot(piece);
+        self.counts[player][kind] += 1;
+    }
+
+    pub(super) fn remove(&mut self, piece: Piece) {
+        let (player, kind) = Self::get_slot(piece);
+        self.counts[player][kind] -= 1;
+    }
+
+    pub(super) fn get_count(&self, piece: Piece) -> u8 {
+        let (player, kind) = Self::get_slot(piece);
+        self.counts[player][kind]
+    }
+
+    /// The pieces `player` holds with how many of each, from pawns to queens.
+    pub(super) fn get_pieces(&self, player: Color) -> Vec<(Piece, u8)> {
+        [
+            Piece::Pawn(player),
+            Piece::Knight(player),
+            Piece::Bishop(player),
+            Piece::Rook(player),
+            Piece::Queen(player),
+        ]
+        .into_iter()
+        .map(|piece| (piece, self.get_count(piece)))
+        .filter(|&(_, count)| count > 0)
+        .collect()
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.counts == [[0; 5]; 2]
+    }
+
+    /// The pockets as written in brackets after the FEN piece placement, white's pieces first and
+    /// each player's from queens to pawns, e.g. `Qpp`.
+    pub(super) fn get_fen_str(&self) -> String {
+        let mut fen = String::new();
+        for player in [Color::White, Color::Black] {
+            for (piece, count) in self.get_pieces(player).into_iter().rev() {
+                for _ in 0..count {
+                    fen.push(piece.get_fen_char());
+                }
+            }
+        }
+        fen
+    }
+
+    pub(super) fn get_zobrist_key(&self) -> u64 {
+        [Color::White, Color::Black]
+            .into_iter()
+            .flat_map(|player| self.get_pieces(player))
+            .fold(0, |key, (piece, count)| {
+                key ^ zobrist::get_pocket_key(piece, count)
+            })
+    }
+}
+
+impl FromStr for Pockets {
+    type Err = ChessError;
+
+    /// Reads the pieces in a pocket field, e.g. `Qpp`, in any order. `-` stands for empty
+    /// pockets.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ChessError::FenPockets(s.to_string());
+        let mut pockets = Self::default();
+        if s == "-" {
+            return Ok(pockets);
+        }
+        for c in s.chars() {
+            let piece = Piece::from_fen_char(c)
+                .filter(|piece| !matches!(piece, Piece::King(..)))
+                .ok_or_else(error)?;
+            if pockets.get_count(piece) == MAX_COUNT {
+                return Err(error());
+            }
+            pockets.add(piece);
+        }
+        Ok(pockets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fen_str() {
+        let pockets: Pockets = "pnQpB".parse().unwrap();
+        assert_eq!(pockets.get_count(Piece::Pawn(Color::Black)), 2);
+        assert_eq!(pockets.get_fen_str(), "QBnpp");
+        assert!("-".parse::<Pockets>().unwrap().is_empty());
+        assert!("Qk".parse::<Pockets>().is_err());
+        assert!("P".repeat(17).parse::<Pockets>().is_err());
+    }
+}
@@ -1,11 +1,12 @@
 use std::{
     fmt,
     ops::{Add, AddAssign, Sub},
+    str::FromStr,
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::displacement::Displacement;
+use crate::{displacement::Displacement, result::ChessError};
 
 const FILES: [char; 8] = ['a', 'b', 'c', 'd', 'e', 'f', 'g', 'h'];
 const RANKS: [char; 8] = ['1', '2', '3', '4', '5', '6', '7', '8'];
@@ -17,16 +18,26 @@ pub struct Position {
 }
 
 impl Position {
-    pub(super) const BLACK_KING: Position = Position::new(4, 7);
-    pub(super) const BLACK_KINGSIDE_ROOK: Position = Position::new(7, 7);
-    pub(super) const BLACK_QUEENSIDE_ROOK: Position = Position::new(0, 7);
-    pub(super) const WHITE_KING: Position = Position::new(4, 0);
-    pub(super) const WHITE_KINGSIDE_ROOK: Position = Position::new(7, 0);
-    pub(super) const WHITE_QUEENSIDE_ROOK: Position = Position::new(0, 0);
-
     pub const fn new(x: usize, y: usize) -> Self {
         Self { x, y }
     }
+
+    /// The square's index from 0 for a1 to 63 for h8.
+    pub(super) fn get_index(&self) -> usize {
+        self.y * 8 + self.x
+    }
+
+    pub(super) fn from_index(index: usize) -> Self {
+        Self::new(index % 8, index / 8)
+    }
+
+    pub(super) fn get_file_char(&self) -> char {
+        FILES[self.x]
+    }
+
+    pub(super) fn get_rank_char(&self) -> char {
+        RANKS[self.y]
+    }
 }
 
 impl fmt::Display for Position {
@@ -35,6 +46,23 @@ impl fmt::Display for Position {
     }
 }
 
+impl FromStr for Position {
+    type Err = ChessError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file), Some(rank), None) => {
+                let x = FILES.iter().position(|&c| c == file);
+                let y = RANKS.iter().position(|&c| c == rank);
+                x.zip(y).map(|(x, y)| Self::new(x, y))
+            }
+            _ => None,
+        }
+        .ok_or_else(|| ChessError::InvalidSquare(s.to_string()))
+    }
+}
+
 impl Add<Displacement> for Position {
     type Output = Self;
 
@@ -82,4 +110,13 @@ mod tests {
         }
         assert_eq!(p, Position { x: 10, y: 10 })
     }
+
+    #[test]
+    fn test_position_from_str() {
+        assert_eq!("e4".parse::<Position>().unwrap(), Position::new(4, 3));
+        assert_eq!("h8".parse::<Position>().unwrap(), Position::new(7, 7));
+        assert!("i1".parse::<Position>().is_err());
+        assert!("a9".parse::<Position>().is_err());
+        assert!("a10".parse::<Position>().is_err());
+    }
 }
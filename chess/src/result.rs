@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::color::Color;
+
 pub type ChessResult = Result<(), ChessError>;
 #[derive(Debug, Error)]
 pub enum ChessError {
@@ -13,4 +15,75 @@ pub enum ChessError {
     GameIsInDraw,
     #[error("Moved after time ran out")]
     Timeout,
+    #[error("The game is already over")]
+    GameOver,
+    #[error("There is no draw offer to respond to")]
+    NoDrawOffer,
+    #[error("Players cannot respond to their own draw offer")]
+    OwnDrawOffer,
+    #[error("There is no draw to claim")]
+    NoDrawToClaim,
+    #[error("There is no move to take back")]
+    NoMoveToTakeBack,
+    #[error("There is no takeback request to respond to")]
+    NoTakebackRequest,
+    #[error("Players cannot respond to their own takeback request")]
+    OwnTakebackRequest,
+    #[error("There is no such move in the history")]
+    NotInHistory,
+    #[error("The current move is not in a variation")]
+    NotInVariation,
+    #[error("Invalid square '{0}'")]
+    InvalidSquare(String),
+    #[error("FEN must have 6 fields, or 7 with the remaining checks of Three-check, found {0}")]
+    FenFieldCount(usize),
+    #[error("FEN piece placement must have 8 ranks, found {0}")]
+    FenRankCount(usize),
+    #[error("FEN rank {0} does not describe exactly 8 squares")]
+    FenRankLength(usize),
+    #[error("Invalid FEN piece '{0}'")]
+    FenPiece(char),
+    #[error("FEN must have exactly one {0:?} king")]
+    FenKingCount(Color),
+    #[error("FEN has a pawn on rank {0}")]
+    FenPawnOnBackRank(usize),
+    #[error("Invalid FEN active color '{0}'")]
+    FenActiveColor(String),
+    #[error("Invalid FEN castling rights '{0}'")]
+    FenCastlingRights(String),
+    #[error("Invalid FEN en passant square '{0}'")]
+    FenEnPassant(String),
+    #[error("Invalid FEN halfmove clock '{0}'")]
+    FenHalfmoveClock(String),
+    #[error("Invalid FEN fullmove number '{0}'")]
+    FenFullmoveNumber(String),
+    #[error("Invalid FEN remaining checks '{0}'")]
+    FenRemainingChecks(String),
+    #[error("Invalid FEN pockets '{0}'")]
+    FenPockets(String),
+    #[error("FEN has the side not to move in check")]
+    FenOpponentInCheck,
+    #[error("Chess960 positions are numbered 0 to 959, got {0}")]
+    Chess960Index(u16),
+    #[error("Unknown variant '{0}'")]
+    UnknownVariant(String),
+    #[error("A time control needs a period, and a number of moves for every period but the last")]
+    TimeControlPeriods,
+    #[error("Invalid or unsupported time control '{0}'")]
+    InvalidTimeControl(String),
+    #[error("Invalid SAN '{0}'")]
+    InvalidSan(String),
+    #[error("Illegal SAN move '{0}'")]
+    IllegalSan(String),
+    #[error("Ambiguous SAN move '{0}'")]
+    AmbiguousSan(String),
+    #[error("Invalid PGN: {0}")]
+    PgnSyntax(String),
+    #[error("Invalid move '{token}' in game {game}, ply {ply}: {source}")]
+    PgnMove {
+        game: usize,
+        ply: usize,
+        token: String,
+        source: Box<ChessError>,
+    },
 }
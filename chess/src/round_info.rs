@@ -1,4 +1,28 @@
+use crate::move_time::MoveTime;
+
+/// A round of the mainline: a move of each player.
 pub struct RoundInfo {
+    pub number: usize,
     pub white_string: String,
     pub black_string: String,
+    pub white_time: Option<MoveTime>,
+    pub black_time: Option<MoveTime>,
+    /// The paths to go to the moves with `Game::go_to_move`, `None` for a missing move.
+    pub white_path: Option<Vec<usize>>,
+    pub black_path: Option<Vec<usize>>,
+    /// The variations branching off the round, each followed by its own.
+    pub variations: Vec<VariationInfo>,
+}
+
+/// A line of moves replacing part of the mainline, or of another variation one level up.
+pub struct VariationInfo {
+    /// 1 for a variation of the mainline.
+    pub depth: usize,
+    pub moves: Vec<VariationMove>,
+}
+
+pub struct VariationMove {
+    /// The move in SAN, with its number where needed, e.g. `12... Nf6`.
+    pub text: String,
+    pub path: Vec<usize>,
 }
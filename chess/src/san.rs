@@ -0,0 +1,306 @@
+use crate::{
+    board_state::BoardState, castling_rights::CastlingSide, color::Color, game::Game, moves::Move,
+    piece::Piece, position::Position, result::ChessError,
+};
+
+/// How pieces are written in SAN: `Nf3` or `♞f3`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Notation {
+    #[default]
+    Ascii,
+    Figurine,
+}
+
+impl Notation {
+    fn get_piece_str(self, piece: Piece) -> String {
+        match self {
+            Notation::Ascii => piece.get_san_char().to_string(),
+            Notation::Figurine => piece.to_string(),
+        }
+    }
+}
+
+impl Move {
+    /// Formats the move, played from `state`, in Standard Algebraic Notation, e.g. `Nbxd2+`, or
+    /// `N@f3` for a drop.
+    pub(super) fn to_san(self, state: &BoardState, notation: Notation) -> String {
+        let mut san = String::new();
+        if let Some(piece) = self.drop {
+            san.push_str(&notation.get_piece_str(piece));
+            san.push('@');
+            san.push_str(&self.to.to_string());
+        } else if let Some(side) = state.get_castling_side(&self) {
+            san.push_str(match side {
+                CastlingSide::Kingside => "O-O",
+                CastlingSide::Queenside => "O-O-O",
+            });
+        } else {
+            let piece = state
+                .get_piece(&self.from)
+                .expect("a move must start on a piece");
+            let is_capture = state.get_piece(&self.to).is_some()
+                || (piece.is_pawn() && self.from.x != self.to.x);
+            if piece.is_pawn() {
+                if is_capture {
+                    san.push(self.from.get_file_char());
+                }
+            } else {
+                san.push_str(&notation.get_piece_str(piece));
+                san.push_str(&self.get_disambiguation(piece, state));
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&self.to.to_string());
+            if let Some(promotion) = self.promotion {
+                san.push('=');
+                san.push_str(&notation.get_piece_str(promotion));
+            }
+        }
+
+        let mut next_state = *state;
+        next_state.move_piece(&self);
+        if next_state.is_in_check() {
+            san.push(if next_state.get_legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    /// Finds the legal move in `game` that is described by a Standard Algebraic Notation token,
+    /// e.g. `exd5`, `Nbd2`, `O-O`, `e8=Q+` or the drop `N@f3`.
+    pub fn from_san(game: &Game, san: &str) -> Result<Self, ChessError> {
+        let pattern = SanPattern::parse(san, game.get_current_player())
+            .ok_or_else(|| ChessError::InvalidSan(san.to_string()))?;
+        let state = game.get_current_state();
+        let mut candidates = game
+            .get_valid_moves()
+            .iter()
+            .filter(|mv| pattern.matches(mv, state));
+        match (candidates.next(), candidates.next()) {
+            (Some(&mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(ChessError::AmbiguousSan(san.to_string())),
+            (None, _) => Err(ChessError::IllegalSan(san.to_string())),
+        }
+    }
+
+    /// The origin file and/or rank needed to tell this move apart from other moves of the same
+    /// kind of piece to the same square.
+    fn get_disambiguation(&self, piece: Piece, state: &BoardState) -> String {
+        let others: Vec<_> = state
+            .get_legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                mv.to == self.to && mv.from != self.from && state.get_piece(&mv.from) == Some(piece)
+            })
+            .map(|mv| mv.from)
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|from| from.x != self.from.x) {
+            self.from.get_file_char().to_string()
+        } else if others.iter().all(|from| from.y != self.from.y) {
+            self.from.get_rank_char().to_string()
+        } else {
+            self.from.to_string()
+        }
+    }
+}
+
+/// What a SAN token says about the move it describes.
+enum SanPattern {
+    Castling(CastlingSide),
+    Drop {
+        piece: Piece,
+        to: Position,
+    },
+    Piece {
+        piece: Piece,
+        to: Position,
+        promotion: Option<Piece>,
+        file: Option<char>,
+        rank: Option<char>,
+    },
+}
+
+impl SanPattern {
+    fn parse(san: &str, player: Color) -> Option<Self> {
+        let get_piece = |c: char| {
+            Piece::from_fen_char(match player {
+                Color::White => c,
+                Color::Black => c.to_ascii_lowercase(),
+            })
+        };
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        match san {
+            "O-O" | "0-0" => return Some(Self::Castling(CastlingSide::Kingside)),
+            "O-O-O" | "0-0-0" => return Some(Self::Castling(CastlingSide::Queenside)),
+            _ => (),
+        }
+        // a pawn drop may leave out the `P`
+        if let Some((piece, to)) = san.split_once('@') {
+            let piece = match piece.chars().collect::<Vec<_>>()[..] {
+                [] => Piece::Pawn(player),
+                [c] if "PNBRQ".contains(c) => get_piece(c)?,
+                _ => return None,
+            };
+            return Some(Self::Drop {
+                piece,
+                to: to.parse().ok()?,
+            });
+        }
+
+        let (san, promotion) = match san.split_once('=') {
+            Some((san, promotion)) => match promotion.chars().collect::<Vec<_>>()[..] {
+                [c] if "NBRQ".contains(c) => (san, get_piece(c)),
+                _ => return None,
+            },
+            None => (san, None),
+        };
+        let (piece, san) = match san.chars().next()? {
+            c if "NBRQK".contains(c) => (get_piece(c)?, &san[1..]),
+            _ => (Piece::Pawn(player), san),
+        };
+        let to = san.get(san.len().checked_sub(2)?..)?.parse().ok()?;
+        let (mut file, mut rank) = (None, None);
+        for c in san[..san.len() - 2].trim_end_matches('x').chars() {
+            match c {
+                'a'..='h' if file.is_none() && rank.is_none() => file = Some(c),
+                '1'..='8' if rank.is_none() => rank = Some(c),
+                _ => return None,
+            }
+        }
+        Some(Self::Piece {
+            piece,
+            to,
+            promotion,
+            file,
+            rank,
+        })
+    }
+
+    fn matches(&self, mv: &Move, state: &BoardState) -> bool {
+        let castling_side = state.get_castling_side(mv);
+        match *self {
+            Self::Castling(side) => castling_side == Some(side),
+            Self::Drop { piece, to } => mv.drop == Some(piece) && mv.to == to,
+            Self::Piece {
+                piece: kind,
+                to,
+                promotion,
+                file,
+                rank,
+            } => {
+                castling_side.is_none()
+                    && mv.drop.is_none()
+                    && state.get_piece(&mv.from) == Some(kind)
+                    && mv.to == to
+                    && mv.promotion == promotion
+                    && match (kind, file) {
+                        // a pawn capture names the file it captures from
+                        (Piece::Pawn(_), None) => mv.from.x == to.x,
+                        (_, file) => file.is_none_or(|file| file == mv.from.get_file_char()),
+                    }
+                    && rank.is_none_or(|rank| rank == mv.from.get_rank_char())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san(fen: &str, lan: &str) -> String {
+        let game = Game::from_fen(fen).unwrap();
+        let state = *game.get_current_state();
+        Move::from_lan(lan).unwrap().to_san(&state, Notation::Ascii)
+    }
+
+    #[test]
+    fn test_to_san() {
+        const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(san(START, "e2e4"), "e4");
+        assert_eq!(san(START, "g1f3"), "Nf3");
+        assert_eq!(san("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "e4d5"), "exd5");
+        assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"), "O-O");
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1c1"), "O-O-O");
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8#");
+        assert_eq!(san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1"), "Rad1");
+        assert_eq!(san("4k3/8/8/8/8/8/8/1N1NK3 w - - 0 1", "b1c3"), "Nbc3");
+        assert_eq!(san("4k3/8/8/8/8/1N6/8/1N2K3 w - - 0 1", "b1d2"), "N1d2");
+        assert_eq!(san("4k3/8/8/8/8/1N6/8/1N2KN2 w - - 0 1", "b1d2"), "Nb1d2");
+        assert_eq!(san("1r5k/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n"), "axb8=N");
+        assert_eq!(san("7k/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), "a8=Q+");
+    }
+
+    #[test]
+    fn test_to_figurine_san() {
+        let state = *Game::from_fen("7k/P7/8/8/8/8/8/1N2K3 w - - 0 1")
+            .unwrap()
+            .get_current_state();
+        let figurine = |lan| {
+            Move::from_lan(lan)
+                .unwrap()
+                .to_san(&state, Notation::Figurine)
+        };
+        assert_eq!(figurine("b1c3"), "♞c3");
+        assert_eq!(figurine("a7a8q"), "a8=♛+");
+    }
+
+    #[test]
+    fn test_from_san() {
+        let from_san = |fen: &str, san: &str| Move::from_san(&Game::from_fen(fen).unwrap(), san);
+        let lan = |lan: &str| Move::from_lan(lan).unwrap();
+        let game = Game::new();
+        assert_eq!(Move::from_san(&game, "e4").unwrap(), lan("e2e4"));
+        assert_eq!(Move::from_san(&game, "Nf3!?").unwrap(), lan("g1f3"));
+        assert_eq!(
+            from_san("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1", "O-O-O").unwrap(),
+            lan("e1c1")
+        );
+        assert_eq!(
+            from_san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6").unwrap(),
+            lan("e5d6")
+        );
+        assert_eq!(
+            from_san("4k3/8/8/8/8/1N6/8/1N2KN2 w - - 0 1", "Nb1d2").unwrap(),
+            lan("b1d2")
+        );
+        assert_eq!(
+            from_san("1r5k/P7/8/8/8/8/8/4K3 w - - 0 1", "axb8=N").unwrap(),
+            lan("a7b8n")
+        );
+        assert_eq!(
+            from_san("4k3/8/8/8/8/8/4p3/K7 b - - 0 1", "e1=R").unwrap(),
+            lan("e2e1r")
+        );
+        assert!(matches!(
+            from_san("4k3/8/8/8/8/8/8/1N1NK3 w - - 0 1", "Nc3"),
+            Err(ChessError::AmbiguousSan(..))
+        ));
+        assert!(matches!(
+            Move::from_san(&game, "e5"),
+            Err(ChessError::IllegalSan(..))
+        ));
+        // a pawn only captures with the file it comes from
+        assert!(matches!(
+            from_san("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "d5"),
+            Err(ChessError::IllegalSan(..))
+        ));
+        assert_eq!(
+            from_san("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1", "exd5").unwrap(),
+            lan("e4d5")
+        );
+        assert!(matches!(
+            Move::from_san(&game, "Zz9"),
+            Err(ChessError::InvalidSan(..))
+        ));
+    }
+}
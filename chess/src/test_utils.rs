@@ -0,0 +1,23 @@
+//! Helpers shared by the unit tests.
+
+use crate::{clock::ManualClock, game::Game, moves::Move, time_control::TimeControl};
+
+/// A game from `fen` whose clocks only move when the returned clock is advanced.
+pub(crate) fn new_game_with_clock(
+    fen: &str,
+    time_control: Option<TimeControl>,
+) -> (Game, ManualClock) {
+    let clock = ManualClock::new();
+    let mut builder = Game::builder().clock(clock.clone());
+    if let Some(time_control) = time_control {
+        builder = builder.time_control(time_control);
+    }
+    (builder.fen(fen).unwrap().build().unwrap(), clock)
+}
+
+/// Plays moves given in long algebraic notation.
+pub(crate) fn play(game: &mut Game, moves: &[&str]) {
+    for lan in moves {
+        game.move_piece(Move::from_lan(lan).unwrap()).unwrap();
+    }
+}
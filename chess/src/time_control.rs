@@ -0,0 +1,227 @@
+use std::{fmt, str::FromStr};
+
+use web_time::Duration;
+
+use crate::result::ChessError;
+
+const DEFAULT_TIME: Duration = Duration::from_secs(3600);
+
+/// The time a player gets on each move on top of their main time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bonus {
+    #[default]
+    None,
+    /// Fischer increment: added to the clock after every move.
+    Increment(Duration),
+    /// Bronstein delay: the time used on a move is added back after it, up to the delay.
+    Bronstein(Duration),
+    /// Simple or US delay: the clock only starts running after the delay on every move.
+    Delay(Duration),
+}
+
+/// One period of a time control: `time` for the next `moves` moves, or for the rest of the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Period {
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Bonus,
+}
+
+/// The time each player gets for their moves, in one or more periods, e.g. 40 moves in 90
+/// minutes, then 30 minutes for the rest of the game, both with 30 seconds added per move. Unused
+/// time carries over into the next period. The last period repeats if it has a move limit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    periods: Vec<Period>,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIME)
+    }
+}
+
+impl TimeControl {
+    /// `time` for the whole game.
+    pub fn new(time: Duration) -> Self {
+        Self::with_bonus(time, Bonus::None)
+    }
+
+    /// `time` for the whole game, with `bonus` on every move.
+    pub fn with_bonus(time: Duration, bonus: Bonus) -> Self {
+        Self {
+            periods: vec![Period {
+                moves: None,
+                time,
+                bonus,
+            }],
+        }
+    }
+
+    /// A time control of several periods. Only the last period may leave out its number of
+    /// moves.
+    pub fn with_periods(periods: Vec<Period>) -> Result<Self, ChessError> {
+        let Some((last, others)) = periods.split_last() else {
+            return Err(ChessError::TimeControlPeriods);
+        };
+        if last.moves == Some(0) || others.iter().any(|period| period.moves.unwrap_or(0) == 0) {
+            return Err(ChessError::TimeControlPeriods);
+        }
+        Ok(Self { periods })
+    }
+
+    pub fn get_periods(&self) -> &[Period] {
+        &self.periods
+    }
+
+    /// The period a player is in after making `moves` moves, and whether it started with the
+    /// last of them.
+    fn find_period(&self, moves: u32) -> (&Period, bool) {
+        let mut index = 0;
+        let mut start = 0;
+        loop {
+            let period = &self.periods[index];
+            match period.moves {
+                Some(period_moves) if moves >= start + period_moves => {
+                    start += period_moves;
+                    index = (index + 1).min(self.periods.len() - 1);
+                }
+                _ => return (period, moves > 0 && moves == start),
+            }
+        }
+    }
+
+    /// The bonus of the period a player is in after making `moves` moves.
+    pub(super) fn get_bonus(&self, moves: u32) -> Bonus {
+        self.find_period(moves).0.bonus
+    }
+
+    /// The time a player gets when a new period starts after their `moves`-th move.
+    pub(super) fn get_time_added_after(&self, moves: u32) -> Duration {
+        match self.find_period(moves) {
+            (period, true) => period.time,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// The value of the PGN `TimeControl` tag, e.g. `40/5400+30:1800+30`. Delays, which PGN has no
+    /// notation for, are left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, period) in self.periods.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            if let Some(moves) = period.moves {
+                write!(f, "{moves}/")?;
+            }
+            write!(f, "{}", period.time.as_secs())?;
+            match period.bonus {
+                Bonus::Increment(time) => write!(f, "+{}", time.as_secs())?,
+                Bonus::None | Bonus::Bronstein(_) | Bonus::Delay(_) => (),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = ChessError;
+
+    /// Reads a PGN `TimeControl` tag value. The unknown `?`, untimed `-` and sandglass `*` controls
+    /// are not supported.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ChessError::InvalidTimeControl(s.to_string());
+        let seconds = |field: &str| field.parse().map(Duration::from_secs).map_err(|_| error());
+        let periods = s
+            .split(':')
+            .map(|field| {
+                let (moves, field) = match field.split_once('/') {
+                    Some((moves, field)) => (Some(moves.parse().map_err(|_| error())?), field),
+                    None => (None, field),
+                };
+                let (time, bonus) = match field.split_once('+') {
+                    Some((time, increment)) => (time, Bonus::Increment(seconds(increment)?)),
+                    None => (field, Bonus::None),
+                };
+                Ok(Period {
+                    moves,
+                    time: seconds(time)?,
+                    bonus,
+                })
+            })
+            .collect::<Result<_, ChessError>>()?;
+        Self::with_periods(periods).map_err(|_| error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pgn_tag() {
+        for tag in ["300", "180+2", "40/5400+30:1800+30", "40/7200"] {
+            assert_eq!(tag.parse::<TimeControl>().unwrap().to_string(), tag);
+        }
+        let delay = TimeControl::with_bonus(
+            Duration::from_secs(900),
+            Bonus::Delay(Duration::from_secs(5)),
+        );
+        assert_eq!(delay.to_string(), "900");
+        let classical: TimeControl = "40/5400:1800+30".parse().unwrap();
+        assert_eq!(
+            classical.get_periods(),
+            [
+                Period {
+                    moves: Some(40),
+                    time: Duration::from_secs(5400),
+                    bonus: Bonus::None,
+                },
+                Period {
+                    moves: None,
+                    time: Duration::from_secs(1800),
+                    bonus: Bonus::Increment(Duration::from_secs(30)),
+                },
+            ]
+        );
+        for tag in [
+            "?",
+            "-",
+            "*180",
+            "",
+            "300+",
+            "1800:40/5400",
+            "0/60",
+            "900d5",
+        ] {
+            assert!(tag.parse::<TimeControl>().is_err(), "{tag}");
+        }
+    }
+
+    #[test]
+    fn test_periods() {
+        let classical: TimeControl = "40/5400:1800+30".parse().unwrap();
+        assert_eq!(classical.get_bonus(39), Bonus::None);
+        assert_eq!(classical.get_time_added_after(39), Duration::ZERO);
+        assert_eq!(
+            classical.get_time_added_after(40),
+            Duration::from_secs(1800)
+        );
+        assert_eq!(
+            classical.get_bonus(40),
+            Bonus::Increment(Duration::from_secs(30))
+        );
+        assert_eq!(classical.get_time_added_after(80), Duration::ZERO);
+
+        // the last period repeats
+        let repeating: TimeControl = "40/7200".parse().unwrap();
+        assert_eq!(repeating.get_time_added_after(0), Duration::ZERO);
+        assert_eq!(
+            repeating.get_time_added_after(80),
+            Duration::from_secs(7200)
+        );
+        assert_eq!(repeating.get_time_added_after(81), Duration::ZERO);
+    }
+}
@@ -1,91 +1,166 @@
-use web_time::{Duration, Instant};
+use std::sync::Arc;
 
-use crate::color::Color;
+use web_time::Duration;
 
-pub(super) const DEFAULT_DURATION: Duration = Duration::from_secs(3600);
+use crate::{
+    clock::Clock,
+    color::Color,
+    time_control::{Bonus, TimeControl},
+};
 
 #[derive(Clone)]
 pub(super) struct Timer {
-    white_time: Duration,
-    black_time: Duration,
-    time_started: Option<Instant>,
+    clock: Arc<dyn Clock>,
+    time_control: TimeControl,
+    /// The time left on each player's clock when their turn started.
+    times: [Duration; 2],
+    /// How many moves each player has made.
+    moves: [u32; 2],
+    /// The time of `clock` when the running clock was started.
+    time_started: Option<Duration>,
+    /// How long the player to move used on their move before the clock was last paused.
+    move_elapsed: Duration,
     current_player: Color,
 }
 
-impl Default for Timer {
-    fn default() -> Self {
+impl Timer {
+    pub(super) fn new(
+        clock: Arc<dyn Clock>,
+        time_control: TimeControl,
+        first_player: Color,
+    ) -> Self {
+        let start_time = time_control.get_periods()[0].time;
         Self {
-            white_time: DEFAULT_DURATION,
-            black_time: DEFAULT_DURATION,
+            clock,
+            time_control,
+            times: [start_time; 2],
+            moves: [0; 2],
             time_started: None,
-            current_player: Color::White,
+            move_elapsed: Duration::ZERO,
+            current_player: first_player,
         }
     }
-}
 
-impl Timer {
-    pub(super) fn with_duration(start_time: Duration) -> Self {
-        Self {
-            white_time: start_time,
-            black_time: start_time,
-            time_started: None,
-            current_player: Color::White,
-        }
+    pub(super) fn get_clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    pub(super) fn get_time_control(&self) -> &TimeControl {
+        &self.time_control
     }
 
     pub(super) fn start(&mut self) {
-        self.time_started = Some(Instant::now());
+        self.time_started = Some(self.clock.now());
+    }
+
+    /// How long the running clock has been running.
+    fn get_elapsed(&self, time_started: Duration) -> Duration {
+        self.clock.now().saturating_sub(time_started)
     }
 
-    pub(super) fn stop(&mut self) {
-        self.pause_active_time();
-        self.time_started = None;
+    /// Stops the running clock and returns how long the current move has taken.
+    pub(super) fn stop(&mut self) -> Option<Duration> {
+        self.is_active().then(|| self.pause_active_time())
     }
 
-    fn pause_active_time(&mut self) {
-        let elapsed = self
+    /// Stops the clock of the player to move and returns how long their move has taken, counting
+    /// the time before earlier pauses.
+    fn pause_active_time(&mut self) -> Duration {
+        let time_started = self
             .time_started
             .take()
-            .expect("call `timer.start()` first")
-            .elapsed();
-
-        match self.current_player {
-            Color::White => {
-                self.white_time = self
-                    .white_time
-                    .checked_sub(elapsed)
-                    .unwrap_or(Duration::from_secs(0));
-            }
-            Color::Black => {
-                self.black_time = self
-                    .black_time
-                    .checked_sub(elapsed)
-                    .unwrap_or(Duration::from_secs(0));
+            .expect("call `timer.start()` first");
+        let elapsed = self.get_elapsed(time_started);
+        let player = self.current_player;
+        self.times[player as usize] = self.get_time_after(player, elapsed);
+        self.move_elapsed += elapsed;
+        self.move_elapsed
+    }
+
+    /// The time the player to move, `player`, has left after their clock ran for another
+    /// `elapsed` on the current move. A delay only covers the start of the move, however often
+    /// the clock was paused.
+    fn get_time_after(&self, player: Color, elapsed: Duration) -> Duration {
+        let used = match self.time_control.get_bonus(self.moves[player as usize]) {
+            Bonus::Delay(delay) => {
+                (self.move_elapsed + elapsed).saturating_sub(delay)
+                    - self.move_elapsed.saturating_sub(delay)
             }
+            _ => elapsed,
         };
+        self.times[player as usize].saturating_sub(used)
     }
 
-    pub(super) fn next_player(&mut self) {
-        self.pause_active_time();
+    /// Adds the bonus and the time of a new period to the clock of the player to move, who took
+    /// `elapsed` for their move.
+    fn complete_move(&mut self, elapsed: Duration) {
+        let player = self.current_player as usize;
+        self.times[player] += match self.time_control.get_bonus(self.moves[player]) {
+            Bonus::Increment(increment) => increment,
+            Bonus::Bronstein(delay) => elapsed.min(delay),
+            Bonus::None | Bonus::Delay(..) => Duration::ZERO,
+        };
+        self.moves[player] += 1;
+        self.times[player] += self.time_control.get_time_added_after(self.moves[player]);
+    }
+
+    /// Ends the move of the player to move and starts the opponent's clock. Returns how long the
+    /// move took.
+    pub(super) fn next_player(&mut self) -> Duration {
+        let elapsed = self.pause_active_time();
+        self.complete_move(elapsed);
+        self.move_elapsed = Duration::ZERO;
         self.current_player = !self.current_player;
         self.start();
+        elapsed
     }
 
-    pub(super) fn get_time(&self, player: Color) -> Duration {
-        let current_time = match player {
-            Color::White => self.white_time,
-            Color::Black => self.black_time,
-        };
+    /// Gives the move to `player` without ending a move, when the game goes on from another line.
+    pub(super) fn set_current_player(&mut self, player: Color) {
+        if player != self.current_player {
+            let was_active = self.stop().is_some();
+            self.move_elapsed = Duration::ZERO;
+            self.current_player = player;
+            if was_active {
+                self.start();
+            }
+        }
+    }
+
+    /// Puts the clocks back to `times` after each player made `moves` moves, with `player` to
+    /// move, when moves are taken back. A running clock keeps running, and a stopped one starts
+    /// again if `resume`, unless no move is left.
+    pub(super) fn take_back(
+        &mut self,
+        times: [Duration; 2],
+        moves: [u32; 2],
+        player: Color,
+        resume: bool,
+    ) {
+        let was_active = self.stop().is_some();
+        self.move_elapsed = Duration::ZERO;
+        self.times = times;
+        self.moves = moves;
+        self.current_player = player;
+        if (was_active || resume) && moves != [0; 2] {
+            self.start();
+        }
+    }
 
-        if self.time_started.is_some() && player == self.current_player {
-            current_time
-                .checked_sub(self.time_started.unwrap().elapsed())
-                .unwrap_or(Duration::from_secs(0))
-        } else {
-            current_time
+    pub(super) fn get_time(&self, player: Color) -> Duration {
+        match self.time_started {
+            Some(time_started) if player == self.current_player => {
+                self.get_time_after(player, self.get_elapsed(time_started))
+            }
+            _ => self.times[player as usize],
         }
     }
 
+    /// The time on each clock as of the last move, without the time used since.
+    pub(super) fn get_times_at_last_move(&self) -> [Duration; 2] {
+        self.times
+    }
+
     pub(super) fn get_active_time(&self) -> Duration {
         self.get_time(self.current_player)
     }
@@ -104,3 +179,112 @@ impl Timer {
         self.time_started.is_some()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn new_timer(time_control: TimeControl) -> (Timer, ManualClock) {
+        let clock = ManualClock::new();
+        let timer = Timer::new(Arc::new(clock.clone()), time_control, Color::White);
+        (timer, clock)
+    }
+
+    /// Plays `moves` moves of both players, each taking `elapsed`.
+    fn play(timer: &mut Timer, clock: &ManualClock, moves: u32, elapsed: Duration) {
+        if !timer.is_active() {
+            timer.start();
+        }
+        for _ in 0..moves * 2 {
+            clock.advance(elapsed);
+            timer.next_player();
+        }
+    }
+
+    #[test]
+    fn test_bonus() {
+        let time_control = |bonus| TimeControl::with_bonus(secs(300), bonus);
+        for (bonus, expected) in [
+            (Bonus::None, 280),
+            (Bonus::Increment(secs(3)), 286),
+            // the delay covers 5 of the 10 seconds used on each move
+            (Bonus::Bronstein(secs(5)), 290),
+            (Bonus::Delay(secs(5)), 290),
+        ] {
+            let (mut timer, clock) = new_timer(time_control(bonus));
+            play(&mut timer, &clock, 2, secs(10));
+            assert_eq!(timer.get_time(Color::Black), secs(expected), "{bonus:?}");
+        }
+        // a delay longer than the move costs nothing, and gains nothing
+        let (mut timer, clock) = new_timer(time_control(Bonus::Bronstein(secs(5))));
+        play(&mut timer, &clock, 1, secs(2));
+        assert_eq!(timer.get_time(Color::White), secs(300));
+    }
+
+    #[test]
+    fn test_periods() {
+        let (mut timer, clock) = new_timer("2/60:30+1".parse().unwrap());
+        play(&mut timer, &clock, 1, secs(10));
+        assert_eq!(timer.get_time(Color::White), secs(50));
+        // the second move ends the first period and adds the time of the next
+        play(&mut timer, &clock, 1, secs(10));
+        assert_eq!(timer.get_time(Color::White), secs(70));
+        play(&mut timer, &clock, 1, secs(10));
+        assert_eq!(timer.get_time(Color::White), secs(61));
+    }
+
+    #[test]
+    fn test_get_time_during_turn() {
+        let (mut timer, clock) =
+            new_timer(TimeControl::with_bonus(secs(60), Bonus::Delay(secs(5))));
+        timer.start();
+        clock.advance(secs(3));
+        // the delay runs first
+        assert_eq!(timer.get_active_time(), secs(60));
+        clock.advance(secs(7));
+        assert_eq!(timer.get_time(Color::White), secs(55));
+        assert_eq!(timer.get_time(Color::Black), secs(60));
+        clock.advance(secs(100));
+        assert_eq!(timer.get_active_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let (mut timer, clock) = new_timer(TimeControl::new(secs(60)));
+        timer.start();
+        clock.advance(secs(10));
+        timer.stop();
+        assert!(!timer.is_active());
+        // no time passes while paused
+        clock.advance(secs(30));
+        assert_eq!(timer.get_time(Color::White), secs(50));
+        timer.start();
+        clock.advance(secs(5));
+        assert_eq!(timer.get_time(Color::White), secs(45));
+        timer.next_player();
+        clock.advance(secs(5));
+        assert_eq!(timer.get_time(Color::White), secs(45));
+        assert_eq!(timer.get_time(Color::Black), secs(55));
+    }
+
+    #[test]
+    fn test_delay_across_pauses() {
+        for bonus in [Bonus::Delay(secs(5)), Bonus::Bronstein(secs(5))] {
+            let (mut timer, clock) = new_timer(TimeControl::with_bonus(secs(60), bonus));
+            // three pauses within one move still get a single delay
+            for _ in 0..3 {
+                timer.start();
+                clock.advance(secs(3));
+                timer.stop();
+            }
+            timer.start();
+            assert_eq!(timer.next_player(), secs(9), "{bonus:?}");
+            assert_eq!(timer.get_time(Color::White), secs(56), "{bonus:?}");
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::mem;
+
+use crate::moves::Move;
+
+/// Scores at least this far from zero are mates, counted in plies from the root of the search.
+pub(super) const MATE_THRESHOLD: i32 = 29_000;
+
+/// How the stored score relates to the real score of the position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Bound {
+    Exact,
+    /// The real score is at least the stored one, after a beta cutoff.
+    Lower,
+    /// The real score is at most the stored one, when no move raised alpha.
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+pub(super) struct Entry {
+    key: u64,
+    depth: u8,
+    score: i32,
+    bound: Bound,
+    pub(super) best_move: Option<Move>,
+}
+
+impl Entry {
+    /// The score to return without searching the position again, if the stored search was deep
+    /// enough and its bound settles the window from `alpha` to `beta`.
+    pub(super) fn get_cutoff(&self, depth: u8, alpha: i32, beta: i32, ply: usize) -> Option<i32> {
+        if self.depth < depth {
+            return None;
+        }
+        let score = from_table_score(self.score, ply);
+        match self.bound {
+            Bound::Exact => Some(score),
+            Bound::Lower if score >= beta => Some(score),
+            Bound::Upper if score <= alpha => Some(score),
+            _ => None,
+        }
+    }
+}
+
+/// Mate scores are stored relative to the position, not to the root of the search that found
+/// them, so they stay right when the position is reached at another ply.
+fn to_table_score(score: i32, ply: usize) -> i32 {
+    match score {
+        score if score >= MATE_THRESHOLD => score + ply as i32,
+        score if score <= -MATE_THRESHOLD => score - ply as i32,
+        score => score,
+    }
+}
+
+fn from_table_score(score: i32, ply: usize) -> i32 {
+    match score {
+        score if score >= MATE_THRESHOLD => score - ply as i32,
+        score if score <= -MATE_THRESHOLD => score + ply as i32,
+        score => score,
+    }
+}
+
+/// The results of searched positions by Zobrist key, so that transpositions and the next
+/// iteration of the search can use them.
+pub(super) struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl TranspositionTable {
+    /// A table taking up about `megabytes` of memory.
+    pub(super) fn new(megabytes: usize) -> Self {
+        let count = (megabytes << 20) / mem::size_of::<Option<Entry>>();
+        // a power of two, so that the index is a mask of the key
+        let count = (count.max(2) + 1).next_power_of_two() / 2;
+        Self {
+            entries: vec![None; count],
+        }
+    }
+
+    fn get_index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub(super) fn get(&self, key: u64) -> Option<&Entry> {
+        self.entries[self.get_index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// Stores the result of a search `depth` plies deep, replacing the entry of another position
+    /// or of a shallower search.
+    pub(super) fn store(
+        &mut self,
+        key: u64,
+        depth: u8,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Move>,
+        ply: usize,
+    ) {
+        let index = self.get_index(key);
+        if let Some(entry) = &self.entries[index] {
+            if entry.key == key && entry.depth > depth && bound != Bound::Exact {
+                return;
+            }
+        }
+        self.entries[index] = Some(Entry {
+            key,
+            depth,
+            score: to_table_score(score, ply),
+            bound,
+            best_move,
+        });
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mate_scores() {
+        let mut table = TranspositionTable::new(1);
+        assert!(table.entries.len().is_power_of_two());
+        // a mate in 3 plies found 2 plies from the root is a mate in 5 from the root
+        table.store(42, 3, MATE_THRESHOLD + 995, Bound::Exact, None, 2);
+        let entry = table.get(42).unwrap();
+        assert_eq!(entry.get_cutoff(3, 0, 1, 4), Some(MATE_THRESHOLD + 993));
+        assert_eq!(entry.get_cutoff(4, 0, 1, 4), None);
+        assert!(table.get(43).is_none());
+    }
+}
@@ -1,6 +1,4 @@
-use std::fmt;
-
-use crate::{board_state::BoardState, game_status::GameStatus, moves::Move};
+use crate::{board_state::BoardState, game_status::GameStatus, move_time::MoveTime, moves::Move};
 
 #[derive(Clone, Copy, Default)]
 pub struct Turn {
@@ -8,6 +6,8 @@ pub struct Turn {
     pub(super) mv: Move,
     pub(super) piece_captured: bool,
     pub(crate) status: GameStatus,
+    /// The clocks after the move, unknown for moves read from PGN without clock comments.
+    pub(super) time: Option<MoveTime>,
 }
 
 impl Turn {
@@ -20,22 +20,3 @@ impl Turn {
         }
     }
 }
-
-impl fmt::Display for Turn {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}{}{}{}",
-            self.board_state.get_piece(&self.mv.to).unwrap(),
-            if self.piece_captured { "x" } else { "" },
-            self.mv.to,
-            if matches!(self.status, GameStatus::Check(..)) {
-                "+"
-            } else if matches!(self.status, GameStatus::Checkmate(..)) {
-                "#"
-            } else {
-                ""
-            }
-        )
-    }
-}
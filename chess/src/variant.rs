@@ -0,0 +1,91 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    bitboard::Bitboard, board_state::BoardState, color::Color, game_status::GameStatus,
+    piece::Piece, result::ChessError,
+};
+
+/// The rules a game is played by. Every variant starts from the standard setup, or a Chess960 one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Variant {
+    #[default]
+    Standard,
+    /// A player also wins by getting their king to one of the four center squares.
+    KingOfTheHill,
+    /// A player also wins by checking the opponent's king for the third time.
+    ThreeCheck,
+    /// A capture blows up the capturing piece and every piece but pawns next to the target square,
+    /// and a player wins by blowing up the opponent's king.
+    Atomic,
+    /// A captured piece goes into the capturer's pocket, and instead of moving a player may drop
+    /// a piece from their pocket onto an empty square.
+    Crazyhouse,
+}
+
+impl Variant {
+    pub const ALL: [Self; 5] = [
+        Self::Standard,
+        Self::KingOfTheHill,
+        Self::ThreeCheck,
+        Self::Atomic,
+        Self::Crazyhouse,
+    ];
+
+    /// How a game ends by the variant's own rules in `state`, e.g. after a king reached the
+    /// center in King of the Hill.
+    pub(super) fn get_status(self, state: &BoardState) -> Option<GameStatus> {
+        let players = [Color::White, Color::Black];
+        let kings = |player| state.get_pieces(Piece::King(player));
+        match self {
+            Self::Standard | Self::Crazyhouse => None,
+            Self::KingOfTheHill => players
+                .into_iter()
+                .find(|&player| !(kings(player) & Bitboard::CENTER).is_empty())
+                .map(|player| GameStatus::KingOfTheHill(!player)),
+            Self::ThreeCheck => players
+                .into_iter()
+                .find(|&player| state.checks_given[player as usize] >= 3)
+                .map(|player| GameStatus::ThreeChecks(!player)),
+            Self::Atomic => players
+                .into_iter()
+                .find(|&player| kings(player).is_empty())
+                .map(GameStatus::Exploded),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    /// The name used by the PGN `Variant` tag.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Standard => "Standard",
+            Self::KingOfTheHill => "King of the Hill",
+            Self::ThreeCheck => "Three-check",
+            Self::Atomic => "Atomic",
+            Self::Crazyhouse => "Crazyhouse",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl FromStr for Variant {
+    type Err = ChessError;
+
+    /// Reads a variant name, ignoring case, spaces and dashes. Chess960 is played by the standard
+    /// rules.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "standard" | "chess960" => Ok(Self::Standard),
+            "kingofthehill" => Ok(Self::KingOfTheHill),
+            "threecheck" | "3check" => Ok(Self::ThreeCheck),
+            "atomic" => Ok(Self::Atomic),
+            "crazyhouse" => Ok(Self::Crazyhouse),
+            _ => Err(ChessError::UnknownVariant(s.to_string())),
+        }
+    }
+}
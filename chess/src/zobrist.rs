@@ -0,0 +1,171 @@
+use crate::{color::Color, piece::Piece, position::Position};
+
+/// Random keys XORed together to hash a position, generated at compile time from a fixed seed.
+struct ZobristKeys {
+    pieces: [[u64; 64]; 12],
+    castling_rights: [u64; 4],
+    en_passant_files: [u64; 8],
+    black_to_move: u64,
+    /// One key per player for each of the first three checks given in Three-check.
+    checks_given: [[u64; 3]; 2],
+    /// One key per piece but kings for each of the first sixteen pieces of that kind in a
+    /// Crazyhouse pocket.
+    pockets: [[u64; 16]; 10],
+    /// One key per square for a promoted piece standing on it in Crazyhouse.
+    promoted: [u64; 64],
+}
+
+const KEYS: ZobristKeys = ZobristKeys::new();
+
+impl ZobristKeys {
+    const fn new() -> Self {
+        let mut seed = 0x9e37_79b9_7f4a_7c15;
+        let mut keys = Self {
+            pieces: [[0; 64]; 12],
+            castling_rights: [0; 4],
+            en_passant_files: [0; 8],
+            black_to_move: 0,
+            checks_given: [[0; 3]; 2],
+            pockets: [[0; 16]; 10],
+            promoted: [0; 64],
+        };
+
+        let mut piece = 0;
+        while piece < 12 {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[piece][square] = next_random(&mut seed);
+                square += 1;
+            }
+            piece += 1;
+        }
+        let mut i = 0;
+        while i < 4 {
+            keys.castling_rights[i] = next_random(&mut seed);
+            i += 1;
+        }
+        let mut file = 0;
+        while file < 8 {
+            keys.en_passant_files[file] = next_random(&mut seed);
+            file += 1;
+        }
+        keys.black_to_move = next_random(&mut seed);
+        let mut player = 0;
+        while player < 2 {
+            let mut checks = 0;
+            while checks < 3 {
+                keys.checks_given[player][checks] = next_random(&mut seed);
+                checks += 1;
+            }
+            player += 1;
+        }
+        let mut piece = 0;
+        while piece < 10 {
+            let mut count = 0;
+            while count < 16 {
+                keys.pockets[piece][count] = next_random(&mut seed);
+                count += 1;
+            }
+            piece += 1;
+        }
+        let mut square = 0;
+        while square < 64 {
+            keys.promoted[square] = next_random(&mut seed);
+            square += 1;
+        }
+        keys
+    }
+}
+
+/// xorshift64*
+const fn next_random(seed: &mut u64) -> u64 {
+    *seed ^= *seed >> 12;
+    *seed ^= *seed << 25;
+    *seed ^= *seed >> 27;
+    seed.wrapping_mul(0x2545_f491_4f6c_dd1d)
+}
+
+pub(super) fn get_piece_key(piece: Piece, at: &Position) -> u64 {
+    KEYS.pieces[piece.get_index()][at.get_index()]
+}
+
+pub(super) fn get_castling_right_key(right: usize) -> u64 {
+    KEYS.castling_rights[right]
+}
+
+pub(super) fn get_en_passant_key(at: &Position) -> u64 {
+    KEYS.en_passant_files[at.x]
+}
+
+pub(super) fn get_black_to_move_key() -> u64 {
+    KEYS.black_to_move
+}
+
+/// The key for `player` having given `checks` checks, which is 0 for no checks.
+pub(super) fn get_checks_given_key(player: Color, checks: u8) -> u64 {
+    match checks {
+        0 => 0,
+        _ => KEYS.checks_given[player as usize][checks as usize - 1],
+    }
+}
+
+/// The key for a pocket holding `count` pieces like `piece`, which is 0 for none. Pockets only
+/// hold more than sixteen pieces of a kind in contrived positions, which then share keys.
+pub(super) fn get_pocket_key(piece: Piece, count: u8) -> u64 {
+    match count {
+        0 => 0,
+        _ => KEYS.pockets[piece.get_index()][(count as usize - 1) % 16],
+    }
+}
+
+pub(super) fn get_promoted_key(at: &Position) -> u64 {
+    KEYS.promoted[at.get_index()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::Game, test_utils::play};
+
+    #[test]
+    fn test_transpositions_share_a_hash() {
+        let mut game = Game::new();
+        let mut other_game = Game::new();
+        play(&mut game, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        play(&mut other_game, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+        assert_eq!(game.position_hash(), other_game.position_hash());
+
+        play(&mut game, &["e2e4"]);
+        play(&mut other_game, &["e2e3"]);
+        assert_ne!(game.position_hash(), other_game.position_hash());
+    }
+
+    #[test]
+    fn test_incremental_hash_matches_fen() {
+        let mut game = Game::new();
+        play(
+            &mut game,
+            &[
+                "e2e4", "d7d5", "e4d5", "c7c5", "d5c6", "b8c6", "e1e2", "g8f6",
+            ],
+        );
+        let fen = game.get_fen_str();
+        assert_eq!(
+            game.position_hash(),
+            Game::from_fen(&fen).unwrap().position_hash()
+        );
+    }
+
+    #[test]
+    fn test_en_passant_square_only_counts_when_capturable() {
+        let mut game = Game::new();
+        play(&mut game, &["e2e4"]);
+        let without_en_passant =
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(game.position_hash(), without_en_passant.position_hash());
+
+        let mut game = Game::from_fen("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1").unwrap();
+        play(&mut game, &["e2e4"]);
+        let without_en_passant = Game::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").unwrap();
+        assert_ne!(game.position_hash(), without_en_passant.position_hash());
+    }
+}
@@ -0,0 +1,131 @@
+//! Chess960 start positions, castling and perft node counts from the Chess960 perft suite, see
+//! <https://www.chessprogramming.org/Chess960_Perft_Results>. Run the deeper depths with
+//! `--ignored`.
+
+mod common;
+
+use chess::{ChessError, Game, Move, Variant};
+
+const POSITIONS: [(&str, [u64; 4]); 5] = [
+    (
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        [21, 528, 12_189, 326_672],
+    ),
+    (
+        "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+        [21, 807, 18_002, 667_366],
+    ),
+    (
+        "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+        [20, 479, 10_471, 273_318],
+    ),
+    (
+        "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+        [22, 593, 13_440, 382_958],
+    ),
+    (
+        "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+        [28, 1_120, 31_058, 1_171_749],
+    ),
+];
+
+fn assert_perft(depths: std::ops::RangeInclusive<usize>) {
+    for (fen, nodes) in POSITIONS {
+        for depth in depths.clone() {
+            common::assert_perft(Variant::Standard, fen, depth, nodes[depth - 1]);
+        }
+    }
+}
+
+#[test]
+fn test_perft() {
+    assert_perft(1..=3);
+}
+
+#[test]
+#[ignore]
+fn test_perft_deep() {
+    assert_perft(4..=4);
+}
+
+#[test]
+fn test_start_positions() {
+    let placement = |index| {
+        let fen = Game::chess960(index).unwrap().get_fen_str();
+        fen.split_once('/').unwrap().0.to_string()
+    };
+    assert_eq!(placement(0), "bbqnnrkr");
+    assert_eq!(placement(518), "rnbqkbnr");
+    assert_eq!(placement(959), "rkrnnqbb");
+    assert!(matches!(
+        Game::chess960(960).err().unwrap(),
+        ChessError::Chess960Index(960)
+    ));
+}
+
+#[test]
+fn test_castling_fields() {
+    // Shredder-FEN is written back as X-FEN, with a file only for a rook that is not outermost
+    for (fen, x_fen) in [
+        (
+            "1r1k2r1/8/8/8/8/8/8/1R1K2R1 w GBgb - 0 1",
+            "1r1k2r1/8/8/8/8/8/8/1R1K2R1 w KQkq - 0 1",
+        ),
+        (
+            "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w GBgb - 0 1",
+            "1r2k1r1/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1",
+        ),
+        (
+            "rr2k2r/8/8/8/8/8/8/RR2K2R w KBkb - 0 1",
+            "rr2k2r/8/8/8/8/8/8/RR2K2R w KBkb - 0 1",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ),
+    ] {
+        assert_eq!(Game::from_fen(fen).unwrap().get_fen_str(), x_fen);
+    }
+
+    // `KQkq` name the outermost rooks, even with the king on the e-file
+    let game = Game::from_fen("4k3/8/8/8/8/8/8/4K1R1 w K - 0 1").unwrap();
+    assert_eq!(game.get_fen_str(), "4k3/8/8/8/8/8/8/4K1R1 w K - 0 1");
+    assert!(game.to_pgn().contains("[Variant \"Chess960\"]"));
+    let pgn = "[Variant \"Chess960\"]\n\
+        [FEN \"rnbbkrqn/pppppppp/8/8/8/8/PPPPPPPP/RNBBKRQN w KQkq - 0 1\"]\n\n*";
+    let game = Game::from_pgn(pgn).unwrap().remove(0);
+    assert_eq!(
+        game.get_fen_str(),
+        "rnbbkrqn/pppppppp/8/8/8/8/PPPPPPPP/RNBBKRQN w KQkq - 0 1"
+    );
+}
+
+#[test]
+fn test_castling() {
+    // the king stays on g1 while the rook jumps from h1 to f1
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/6KR w H - 0 1").unwrap();
+    let castle = Move::from_san(&game, "O-O").unwrap();
+    assert_eq!(castle.to_lan(), "g1h1");
+    game.move_piece(castle).unwrap();
+    assert_eq!(game.get_fen_str(), "4k3/8/8/8/8/8/8/5RK1 b - - 1 1");
+
+    // king and rook swap squares
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/2RK4 w C - 0 1").unwrap();
+    game.move_piece(Move::from_lan("d1c1").unwrap()).unwrap();
+    assert_eq!(game.get_fen_str(), "4k3/8/8/8/8/8/8/2KR4 b - - 1 1");
+
+    // a piece between the rook and its destination blocks castling
+    let game = Game::from_fen("4k3/8/8/8/8/8/8/RNK5 w A - 0 1").unwrap();
+    assert!(Move::from_san(&game, "O-O-O").is_err());
+}
+
+#[test]
+fn test_pgn() {
+    let mut game = Game::chess960(0).unwrap();
+    game.move_piece(Move::from_lan("g2g3").unwrap()).unwrap();
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Variant \"Chess960\"]"));
+    assert!(pgn.contains("[FEN \"bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1\"]"));
+    let imported = Game::from_pgn(&pgn).unwrap().remove(0);
+    assert_eq!(imported.get_fen_str(), game.get_fen_str());
+}
@@ -0,0 +1,127 @@
+//! The players' clocks driven by a `ManualClock`.
+
+mod common;
+
+use std::time::Duration;
+
+use chess::{
+    Bonus, ChessError, Color, GameResult, Move, MoveTime, Notation, Outcome, Termination,
+    TimeControl, Variant,
+};
+use common::{new_game_with_clock, play};
+
+#[test]
+fn test_timeout() {
+    let (mut game, clock) = new_game_with_clock(
+        Variant::Standard,
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        Some(TimeControl::with_bonus(
+            Duration::from_secs(60),
+            Bonus::Increment(Duration::from_secs(2)),
+        )),
+    );
+    // the clocks start with the first move
+    clock.advance(Duration::from_secs(30));
+    play(&mut game, &["e2e4"]);
+    assert_eq!(game.get_time(Color::White), Duration::from_secs(62));
+
+    clock.advance(Duration::from_secs(59));
+    play(&mut game, &["e7e5"]);
+    assert_eq!(game.get_time(Color::Black), Duration::from_secs(3));
+
+    clock.advance(Duration::from_secs(61));
+    assert_eq!(game.get_time(Color::White), Duration::from_secs(1));
+    clock.advance(Duration::from_secs(1));
+    assert!(!game.game_over());
+    assert!(matches!(
+        game.move_piece(Move::from_lan("g1f3").unwrap()),
+        Err(ChessError::Timeout)
+    ));
+    game.trigger_timeout();
+    assert_eq!(
+        game.result(),
+        Some(GameResult {
+            outcome: Outcome::BlackWins,
+            reason: Termination::Timeout
+        })
+    );
+    // the clocks stop with the game
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(game.get_time(Color::Black), Duration::from_secs(3));
+}
+
+#[test]
+fn test_timeout_against_a_lone_king() {
+    let (mut game, clock) = new_game_with_clock(
+        Variant::Standard,
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        Some(TimeControl::new(Duration::from_secs(10))),
+    );
+    play(&mut game, &["e2e4"]);
+    play(&mut game, &["e8d8"]);
+    // black cannot win with a lone king
+    clock.advance(Duration::from_secs(10));
+    game.trigger_timeout();
+    assert_eq!(
+        game.result().map(|result| result.reason),
+        Some(Termination::TimeoutVsInsufficientMaterial)
+    );
+}
+
+#[test]
+fn test_move_times() {
+    let (mut game, clock) = new_game_with_clock(
+        Variant::Standard,
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        Some(TimeControl::with_bonus(
+            Duration::from_secs(60),
+            Bonus::Increment(Duration::from_secs(1)),
+        )),
+    );
+    play(&mut game, &["d2d4"]);
+    clock.advance(Duration::from_secs(7));
+    play(&mut game, &["d7d5"]);
+    let times = game.get_move_times();
+    assert_eq!(
+        times[1],
+        Some(MoveTime {
+            white_time: Duration::from_secs(61),
+            black_time: Duration::from_secs(54),
+            time_spent: Duration::from_secs(7),
+        })
+    );
+    assert_eq!(times[0].unwrap().time_spent, Duration::ZERO);
+    // the rounds show the times too
+    let rounds = game.get_rounds_info(Notation::Ascii);
+    assert_eq!(rounds[0].black_time, times[1]);
+}
+
+#[test]
+fn test_take_back() {
+    let (mut game, clock) = new_game_with_clock(
+        Variant::Standard,
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        Some(TimeControl::with_bonus(
+            Duration::from_secs(60),
+            Bonus::Increment(Duration::from_secs(1)),
+        )),
+    );
+    play(&mut game, &["e2e4"]);
+    clock.advance(Duration::from_secs(5));
+    play(&mut game, &["e7e5"]);
+    clock.advance(Duration::from_secs(20));
+    play(&mut game, &["g1f3"]);
+    clock.advance(Duration::from_secs(3));
+    // white gets back the time of their move and their clock runs again, black keeps their time
+    game.take_back(1).unwrap();
+    assert_eq!(game.get_time(Color::White), Duration::from_secs(61));
+    assert_eq!(game.get_time(Color::Black), Duration::from_secs(56));
+    assert!(game.is_timer_active());
+    clock.advance(Duration::from_secs(10));
+    play(&mut game, &["b1c3"]);
+    assert_eq!(game.get_time(Color::White), Duration::from_secs(52));
+    assert_eq!(
+        game.get_move_times()[2].unwrap().time_spent,
+        Duration::from_secs(10)
+    );
+}
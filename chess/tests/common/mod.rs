@@ -0,0 +1,42 @@
+//! Helpers shared by the integration tests. Not every test file uses all of them.
+#![allow(dead_code)]
+
+use chess::{Game, ManualClock, Move, TimeControl, Variant};
+
+pub fn new_game(variant: Variant, fen: &str) -> Game {
+    Game::builder()
+        .variant(variant)
+        .fen(fen)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+/// A game whose clocks only move when the returned clock is advanced.
+pub fn new_game_with_clock(
+    variant: Variant,
+    fen: &str,
+    time_control: Option<TimeControl>,
+) -> (Game, ManualClock) {
+    let clock = ManualClock::new();
+    let mut builder = Game::builder().variant(variant).clock(clock.clone());
+    if let Some(time_control) = time_control {
+        builder = builder.time_control(time_control);
+    }
+    (builder.fen(fen).unwrap().build().unwrap(), clock)
+}
+
+/// Plays moves given in long algebraic notation.
+pub fn play(game: &mut Game, moves: &[&str]) {
+    for lan in moves {
+        game.move_piece(Move::from_lan(lan).unwrap()).unwrap();
+    }
+}
+
+pub fn assert_perft(variant: Variant, fen: &str, depth: usize, nodes: u64) {
+    assert_eq!(
+        new_game(variant, fen).perft(depth),
+        nodes,
+        "{variant} perft({depth}) of {fen}"
+    );
+}
@@ -0,0 +1,110 @@
+//! Crazyhouse rules and perft node counts from the perft suite of shakmaty. Run the deeper depths
+//! with `--ignored`.
+
+mod common;
+
+use chess::{Color, Game, Move, Piece, Position, Variant};
+use common::{assert_perft, new_game, play};
+
+const POSITIONS: [(&str, &[u64]); 3] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+        &[20, 400, 8_902],
+    ),
+    ("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1", &[301]),
+    // promoted queen, written with the pockets as a ninth rank
+    ("4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1", &[20, 360, 5_445]),
+];
+
+#[test]
+fn test_perft() {
+    for (fen, nodes) in POSITIONS {
+        for (depth, &nodes) in (1..).zip(nodes) {
+            assert_perft(Variant::Crazyhouse, fen, depth, nodes);
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn test_perft_deep() {
+    assert_perft(Variant::Crazyhouse, POSITIONS[0].0, 4, 197_281);
+    assert_perft(Variant::Crazyhouse, POSITIONS[1].0, 2, 75_353);
+    assert_perft(Variant::Crazyhouse, POSITIONS[2].0, 4, 132_758);
+}
+
+#[test]
+fn test_captures_fill_the_pocket() {
+    let mut game = Game::builder()
+        .variant(Variant::Crazyhouse)
+        .build()
+        .unwrap();
+    play(&mut game, &["e2e4", "d7d5", "e4d5", "d8d5", "b1c3", "d5a2"]);
+    assert_eq!(
+        game.get_fen_str(),
+        "rnb1kbnr/ppp1pppp/8/8/8/2N5/qPPP1PPP/R1BQKBNR[Ppp] w KQkq - 0 4"
+    );
+    assert_eq!(
+        game.get_pocket(Color::White),
+        [(Piece::Pawn(Color::White), 1)]
+    );
+
+    play(&mut game, &["a1a2", "p@e2"]);
+    assert_eq!(
+        game.get_pocket(Color::Black),
+        [(Piece::Pawn(Color::Black), 1)]
+    );
+    assert_eq!(
+        game.get_fen_str(),
+        "rnb1kbnr/ppp1pppp/8/8/8/2N5/RPPPpPPP/2BQKBNR[QPp] w Kkq - 0 5"
+    );
+}
+
+#[test]
+fn test_promoted_pieces_return_as_pawns() {
+    let mut game = new_game(Variant::Crazyhouse, "4k3/1Q~6/8/8/4b3/8/Kpp5/8/ b - - 0 1");
+    play(&mut game, &["e4b7"]);
+    assert_eq!(game.get_fen_str(), "4k3/1b6/8/8/8/8/Kpp5/8[p] w - - 0 2");
+    play(&mut game, &["a2a3", "c2c1q"]);
+    assert_eq!(game.get_fen_str(), "4k3/1b6/8/8/8/K7/1p6/2q~5[p] w - - 0 3");
+}
+
+#[test]
+fn test_drops() {
+    let game = new_game(Variant::Crazyhouse, "4k3/8/8/8/8/8/8/4K3[Pn] w - - 0 1");
+    // pawns cannot be dropped on the first or last rank
+    let squares = game.get_valid_drop_squares(Piece::Pawn(Color::White));
+    assert_eq!(squares.len(), 48);
+    assert!(squares.iter().all(|at| (1..7).contains(&at.y)));
+    assert!(game
+        .get_valid_drop_squares(Piece::Knight(Color::Black))
+        .is_empty());
+    assert_eq!(
+        Move::from_san(&game, "@e4").unwrap(),
+        Move::with_drop(Piece::Pawn(Color::White), Position::new(4, 3))
+    );
+    assert!(Move::from_san(&game, "N@e4").is_err());
+    // pieces in hand are enough to win
+    assert!(!game.game_over());
+
+    // a drop may only block a check
+    let game = new_game(Variant::Crazyhouse, "4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1");
+    let squares = game.get_valid_drop_squares(Piece::Knight(Color::White));
+    assert_eq!(squares.len(), 3);
+    assert!(squares.iter().all(|at| at.y == 0 && (1..4).contains(&at.x)));
+}
+
+#[test]
+fn test_pgn() {
+    let mut game = Game::builder()
+        .variant(Variant::Crazyhouse)
+        .build()
+        .unwrap();
+    play(&mut game, &["e2e4", "d7d5", "e4d5", "g8f6", "P@e6"]);
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Variant \"Crazyhouse\"]"));
+    assert!(pgn.contains("3. P@e6"));
+    let imported = Game::from_pgn(&pgn).unwrap().remove(0);
+    assert_eq!(imported.get_variant(), Variant::Crazyhouse);
+    assert_eq!(imported.get_fen_str(), game.get_fen_str());
+}
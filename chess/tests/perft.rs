@@ -0,0 +1,110 @@
+//! Move generator node counts for the standard perft positions, see
+//! <https://www.chessprogramming.org/Perft_Results>. Run the deeper depths with `--ignored`.
+
+mod common;
+
+use chess::{Game, Variant};
+use common::assert_perft;
+
+const INITIAL: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITION_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const POSITION_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const POSITION_4_MIRRORED: &str =
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+const POSITION_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+const POSITION_6: &str = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+
+#[test]
+fn test_initial_position() {
+    assert_perft(Variant::Standard, INITIAL, 1, 20);
+    assert_perft(Variant::Standard, INITIAL, 2, 400);
+    assert_perft(Variant::Standard, INITIAL, 3, 8_902);
+}
+
+#[test]
+#[ignore]
+fn test_initial_position_deep() {
+    assert_perft(Variant::Standard, INITIAL, 4, 197_281);
+    assert_perft(Variant::Standard, INITIAL, 5, 4_865_609);
+}
+
+#[test]
+fn test_kiwipete() {
+    assert_perft(Variant::Standard, KIWIPETE, 1, 48);
+    assert_perft(Variant::Standard, KIWIPETE, 2, 2_039);
+}
+
+#[test]
+#[ignore]
+fn test_kiwipete_deep() {
+    assert_perft(Variant::Standard, KIWIPETE, 3, 97_862);
+    assert_perft(Variant::Standard, KIWIPETE, 4, 4_085_603);
+}
+
+#[test]
+fn test_position_3() {
+    assert_perft(Variant::Standard, POSITION_3, 1, 14);
+    assert_perft(Variant::Standard, POSITION_3, 2, 191);
+    assert_perft(Variant::Standard, POSITION_3, 3, 2_812);
+}
+
+#[test]
+#[ignore]
+fn test_position_3_deep() {
+    assert_perft(Variant::Standard, POSITION_3, 4, 43_238);
+    assert_perft(Variant::Standard, POSITION_3, 5, 674_624);
+}
+
+#[test]
+fn test_position_4() {
+    for fen in [POSITION_4, POSITION_4_MIRRORED] {
+        assert_perft(Variant::Standard, fen, 1, 6);
+        assert_perft(Variant::Standard, fen, 2, 264);
+    }
+}
+
+#[test]
+#[ignore]
+fn test_position_4_deep() {
+    for fen in [POSITION_4, POSITION_4_MIRRORED] {
+        assert_perft(Variant::Standard, fen, 3, 9_467);
+        assert_perft(Variant::Standard, fen, 4, 422_333);
+    }
+}
+
+#[test]
+fn test_position_5() {
+    assert_perft(Variant::Standard, POSITION_5, 1, 44);
+    assert_perft(Variant::Standard, POSITION_5, 2, 1_486);
+}
+
+#[test]
+#[ignore]
+fn test_position_5_deep() {
+    assert_perft(Variant::Standard, POSITION_5, 3, 62_379);
+    assert_perft(Variant::Standard, POSITION_5, 4, 2_103_487);
+}
+
+#[test]
+fn test_position_6() {
+    assert_perft(Variant::Standard, POSITION_6, 1, 46);
+    assert_perft(Variant::Standard, POSITION_6, 2, 2_079);
+}
+
+#[test]
+#[ignore]
+fn test_position_6_deep() {
+    assert_perft(Variant::Standard, POSITION_6, 3, 89_890);
+    assert_perft(Variant::Standard, POSITION_6, 4, 3_894_594);
+}
+
+#[test]
+fn test_divide() {
+    let game = Game::from_fen(KIWIPETE).unwrap();
+    let divide = game.divide(2);
+    assert_eq!(divide.len(), 48);
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 2_039);
+    let castle = divide.iter().find(|(mv, _)| mv.to_lan() == "e1g1");
+    assert_eq!(castle.map(|(_, nodes)| *nodes), Some(43));
+}
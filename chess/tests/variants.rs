@@ -0,0 +1,179 @@
+//! Rules and perft node counts of the variants. The Atomic and Three-check positions are from the
+//! perft suites of shakmaty, the King of the Hill counts are worked out by hand. Run the deeper
+//! depths with `--ignored`.
+
+mod common;
+
+use chess::{Game, GameResult, Move, Outcome, Termination, Variant};
+use common::{assert_perft, new_game, play};
+
+const ATOMIC: [(&str, [u64; 3]); 6] = [
+    (
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        [20, 400, 8_902],
+    ),
+    (
+        "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+        [40, 1_238, 45_237],
+    ),
+    (
+        "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+        [28, 833, 23_353],
+    ),
+    ("8/8/8/8/8/8/2k5/rR4KR w KQ - 0 1", [18, 180, 4_364]),
+    ("r3k1rR/5K2/8/8/8/8/8/8 b kq - 0 1", [25, 282, 6_753]),
+    ("Rr2k1rR/3K4/3p4/8/8/8/7P/8 w kq - 0 1", [21, 465, 10_631]),
+];
+const THREE_CHECK_KIWIPETE: &str =
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 1+1 0 1";
+
+#[test]
+fn test_atomic_perft() {
+    for (fen, nodes) in ATOMIC {
+        for (depth, nodes) in (1..).zip(nodes) {
+            assert_perft(Variant::Atomic, fen, depth, nodes);
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn test_atomic_perft_deep() {
+    assert_perft(Variant::Atomic, ATOMIC[0].0, 4, 197_326);
+}
+
+#[test]
+fn test_three_check_perft() {
+    assert_perft(Variant::ThreeCheck, THREE_CHECK_KIWIPETE, 1, 48);
+    assert_perft(Variant::ThreeCheck, THREE_CHECK_KIWIPETE, 2, 2_039);
+}
+
+#[test]
+#[ignore]
+fn test_three_check_perft_deep() {
+    // every check ends the game, which leaves fewer nodes than in standard chess
+    assert_perft(Variant::ThreeCheck, THREE_CHECK_KIWIPETE, 3, 97_848);
+    assert_perft(Variant::ThreeCheck, THREE_CHECK_KIWIPETE, 4, 4_081_798);
+}
+
+#[test]
+fn test_king_of_the_hill_perft() {
+    // 8 king moves, each answered by 3 king moves but for Kd4, which wins at once
+    assert_perft(Variant::KingOfTheHill, "7k/8/8/8/8/2K5/8/8 w - - 0 1", 1, 8);
+    assert_perft(
+        Variant::KingOfTheHill,
+        "7k/8/8/8/8/2K5/8/8 w - - 0 1",
+        2,
+        21,
+    );
+    assert_perft(
+        Variant::KingOfTheHill,
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        4,
+        197_281,
+    );
+}
+
+#[test]
+fn test_king_of_the_hill() {
+    let mut game = new_game(Variant::KingOfTheHill, "7k/8/8/8/8/2K5/8/8 w - - 0 1");
+    // bare kings are no draw while a king can walk to the center
+    assert!(!game.game_over());
+    play(&mut game, &["c3d4"]);
+    assert_eq!(
+        game.result(),
+        Some(GameResult {
+            outcome: Outcome::WhiteWins,
+            reason: Termination::KingInTheCenter
+        })
+    );
+    assert!(game.move_piece(Move::from_lan("h8g8").unwrap()).is_err());
+}
+
+#[test]
+fn test_three_check() {
+    let mut game = Game::builder()
+        .variant(Variant::ThreeCheck)
+        .build()
+        .unwrap();
+    assert_eq!(
+        game.get_fen_str(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1"
+    );
+    play(&mut game, &["e2e4", "e7e5", "f1c4", "d7d6", "c4f7"]);
+    assert_eq!(
+        game.get_fen_str(),
+        "rnbqkbnr/ppp2Bpp/3p4/4p3/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 2+3 0 3"
+    );
+    play(&mut game, &["e8f7", "d1h5"]);
+    assert!(!game.game_over());
+
+    // the checks given are part of the position for the repetition rules
+    let mut game = game_after_checks(2);
+    let other_game = game_after_checks(1);
+    assert_ne!(game.position_hash(), other_game.position_hash());
+    play(&mut game, &["b3b5"]);
+    assert_eq!(
+        game.result(),
+        Some(GameResult {
+            outcome: Outcome::WhiteWins,
+            reason: Termination::ThreeChecks
+        })
+    );
+}
+
+fn game_after_checks(checks: u8) -> Game {
+    new_game(
+        Variant::ThreeCheck,
+        &format!("4k3/8/8/8/8/1Q6/8/4K3 w - - {}+3 0 1", 3 - checks),
+    )
+}
+
+#[test]
+fn test_atomic() {
+    // the capture blows up the capturing knight, the rook and the queen but not the pawn
+    let mut game = new_game(Variant::Atomic, "4k3/8/3r4/3pq3/8/3N4/8/4K3 w - - 0 1");
+    play(&mut game, &["d3e5"]);
+    assert_eq!(game.get_fen_str(), "4k3/8/8/3p4/8/8/8/4K3 b - - 0 1");
+    // a king cannot capture, as it would blow itself up
+    let game = new_game(Variant::Atomic, "4k3/8/8/8/8/8/3p4/4K3 w - - 0 1");
+    assert!(game
+        .is_move_valid(&Move::from_lan("e1d2").unwrap())
+        .is_err());
+    // kings next to each other are never in check
+    assert!(!new_game(Variant::Atomic, "8/8/8/8/8/8/3k4/r3K3 w - - 0 1").is_in_check());
+
+    let mut game = new_game(Variant::Atomic, "3nk3/8/8/8/8/8/8/3RK3 w - - 0 1");
+    play(&mut game, &["d1d8"]);
+    assert_eq!(
+        game.result(),
+        Some(GameResult {
+            outcome: Outcome::WhiteWins,
+            reason: Termination::Explosion
+        })
+    );
+}
+
+#[test]
+fn test_pgn() {
+    let mut game = new_game(Variant::Atomic, "3nk3/8/8/8/8/8/8/3RK3 w - - 0 1");
+    play(&mut game, &["d1d8"]);
+    let pgn = game.to_pgn();
+    assert!(pgn.contains("[Variant \"Atomic\"]"));
+    assert!(pgn.contains("1-0"));
+    let imported = Game::from_pgn(&pgn).unwrap().remove(0);
+    assert_eq!(imported.get_variant(), Variant::Atomic);
+    assert_eq!(imported.result(), game.result());
+}
+
+#[test]
+fn test_variant_names() {
+    for variant in Variant::ALL {
+        assert_eq!(variant.to_string().parse::<Variant>().unwrap(), variant);
+    }
+    assert_eq!(
+        "threecheck".parse::<Variant>().unwrap(),
+        Variant::ThreeCheck
+    );
+    assert!("horde".parse::<Variant>().is_err());
+}